@@ -0,0 +1,160 @@
+use crate::excel_processor::{self, percent_of, CustomerData, RankDirection, RankMetric, TopNOptions};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// ABC 分类等级
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AbcBand {
+    A,
+    B,
+    C,
+}
+
+/// ABC 分类参数（各等级占指标合计的比例，%）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AbcOptions {
+    pub a_share: f64,
+    pub b_share: f64,
+    pub c_share: f64,
+    pub metric: RankMetric,
+}
+
+impl Default for AbcOptions {
+    fn default() -> Self {
+        AbcOptions {
+            a_share: 70.0,
+            b_share: 20.0,
+            c_share: 10.0,
+            metric: RankMetric::TotalAmount,
+        }
+    }
+}
+
+impl AbcOptions {
+    fn validate(&self) -> Result<(), String> {
+        if self.a_share <= 0.0 || self.b_share < 0.0 || self.c_share < 0.0 {
+            return Err("ABC分类比例不能为负数，且A类比例必须大于0".to_string());
+        }
+        let sum = self.a_share + self.b_share + self.c_share;
+        if (sum - 100.0).abs() > 0.01 {
+            return Err(format!("ABC分类比例之和必须为100%，当前为 {:.2}%", sum));
+        }
+        Ok(())
+    }
+
+    /// 按进入该客户之前的累计占比确定等级（跨越分界线的客户归入较高等级）
+    fn band_of(&self, cumulative_before: f64) -> AbcBand {
+        if cumulative_before < self.a_share {
+            AbcBand::A
+        } else if cumulative_before < self.a_share + self.b_share {
+            AbcBand::B
+        } else {
+            AbcBand::C
+        }
+    }
+}
+
+/// 带等级标签的客户
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbcCustomer {
+    pub rank: usize,
+    pub band: AbcBand,
+    #[serde(flatten)]
+    pub customer: CustomerData,
+    pub metric_value: f64,
+    pub share: f64,
+    pub cumulative_share: f64,
+}
+
+/// 单个等级的汇总
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbcBandSummary {
+    pub band: AbcBand,
+    pub customer_count: usize,
+    // 客户数占比（%）
+    pub customer_share: f64,
+    // 排名指标合计
+    pub metric_value: f64,
+    // 指标占比（%）
+    pub metric_share: f64,
+    pub total_amount: Money,
+}
+
+/// ABC 分类结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbcResult {
+    pub options: AbcOptions,
+    pub bands: Vec<AbcBandSummary>,
+    pub customers: Vec<AbcCustomer>,
+    pub total_customers: usize,
+    pub metric_total: f64,
+    pub total_amount: Money,
+    pub total_rows: usize,
+    pub process_time_ms: u128,
+}
+
+/// 按累计占比对客户进行 ABC 分类
+pub fn classify(
+    customers: Vec<CustomerData>,
+    options: &AbcOptions,
+    total_rows: usize,
+) -> Result<AbcResult, String> {
+    let start_time = std::time::Instant::now();
+    options.validate()?;
+
+    let ranking = excel_processor::rank_customers(
+        customers,
+        &TopNOptions {
+            n: usize::MAX,
+            metric: options.metric,
+            direction: RankDirection::Desc,
+            min_amount: None,
+        },
+        total_rows,
+    );
+
+    let customers: Vec<AbcCustomer> = ranking
+        .top_customers
+        .into_iter()
+        .map(|ranked| AbcCustomer {
+            rank: ranked.rank,
+            band: options.band_of(ranked.cumulative_share - ranked.share),
+            customer: ranked.customer,
+            metric_value: ranked.metric_value,
+            share: ranked.share,
+            cumulative_share: ranked.cumulative_share,
+        })
+        .collect();
+
+    let total_customers = customers.len();
+    let bands = [AbcBand::A, AbcBand::B, AbcBand::C]
+        .into_iter()
+        .map(|band| {
+            let members = customers.iter().filter(|c| c.band == band);
+            let customer_count = members.clone().count();
+            let metric_value: f64 = members.clone().map(|c| c.metric_value).sum();
+            let total_amount: Money = members.map(|c| c.customer.total_amount).sum();
+
+            AbcBandSummary {
+                band,
+                customer_count,
+                customer_share: percent_of(customer_count as f64, total_customers as f64),
+                metric_value,
+                metric_share: percent_of(metric_value, ranking.metric_total),
+                total_amount,
+            }
+        })
+        .collect();
+
+    Ok(AbcResult {
+        options: options.clone(),
+        bands,
+        customers,
+        total_customers,
+        metric_total: ranking.metric_total,
+        total_amount: ranking.total_amount,
+        total_rows,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
@@ -0,0 +1,236 @@
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::excel_processor::{CustomerData, RankMetric};
+use crate::money::Money;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 没有日期的行归入的月份
+pub const UNKNOWN_MONTH: &str = "未知月份";
+
+/// 聚合单元：某个维度值在某个月份的汇总
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CubeCell {
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
+    pub order_count: u32,
+}
+
+impl CubeCell {
+    pub fn add_row(&mut self, pay_amount: Money, recharge_deduction: Money, total_amount: Money) {
+        self.pay_amount += pay_amount;
+        self.recharge_deduction += recharge_deduction;
+        self.total_amount += total_amount;
+        self.order_count += 1;
+    }
+
+    pub fn add(&mut self, other: &CubeCell) {
+        self.pay_amount += other.pay_amount;
+        self.recharge_deduction += other.recharge_deduction;
+        self.total_amount += other.total_amount;
+        self.order_count += other.order_count;
+    }
+
+    /// 指标值（金额以元为单位，仅用于排序和计算比例）
+    pub fn metric_value(&self, metric: RankMetric) -> f64 {
+        match metric {
+            RankMetric::TotalAmount => self.total_amount.to_yuan(),
+            RankMetric::PayAmount => self.pay_amount.to_yuan(),
+            RankMetric::RechargeDeduction => self.recharge_deduction.to_yuan(),
+            RankMetric::OrderCount => self.order_count as f64,
+        }
+    }
+}
+
+/// 单个维度的聚合表：维度值 → 月份 → 汇总
+pub type DimensionTable = HashMap<String, BTreeMap<String, CubeCell>>;
+
+/// 预聚合数据立方体（维度 × 维度值 × 月份）
+///
+/// 导入时构建并随缓存一起持久化，月度分析和排行直接查询，
+/// 只有订单明细才需要回到原始行数据。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateCube {
+    pub tables: HashMap<String, DimensionTable>,
+    // 客户编码 → 客户名称（第一个非空名称）
+    pub customer_names: HashMap<String, String>,
+}
+
+impl AggregateCube {
+    /// 从列式存储构建（各维度并行聚合，包括自定义维度）
+    pub fn build(store: &ColumnarStore) -> Self {
+        let tables: HashMap<String, DimensionTable> = store
+            .dimension_names()
+            .into_par_iter()
+            .map(|dim| {
+                let table = build_table(store, &dim);
+                (dim, table)
+            })
+            .collect();
+
+        let mut customer_names: HashMap<String, String> = HashMap::new();
+        for i in 0..store.len() {
+            let name = store.customer_name(i);
+            let entry = customer_names.entry(store.customer_code(i).to_string()).or_default();
+            if entry.is_empty() && !name.is_empty() {
+                *entry = name.to_string();
+            }
+        }
+
+        AggregateCube {
+            tables,
+            customer_names,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// 合并另一个立方体（多数据源合并时使用）
+    pub fn merge(&mut self, other: &AggregateCube) {
+        for (dim, other_table) in &other.tables {
+            let table = self.tables.entry(dim.clone()).or_default();
+            for (value, other_months) in other_table {
+                let months = table.entry(value.clone()).or_default();
+                for (month, cell) in other_months {
+                    months.entry(month.clone()).or_default().add(cell);
+                }
+            }
+        }
+
+        for (code, name) in &other.customer_names {
+            let entry = self.customer_names.entry(code.clone()).or_default();
+            if entry.is_empty() && !name.is_empty() {
+                entry.clone_from(name);
+            }
+        }
+    }
+
+    /// 指定维度值的逐月汇总
+    pub fn months_of(&self, analysis_type: &str, target: &str) -> Option<&BTreeMap<String, CubeCell>> {
+        self.tables.get(analysis_type)?.get(target)
+    }
+
+    /// 数据中最早和最晚的月份（不含"未知月份"）
+    pub fn month_range(&self) -> Option<(&str, &str)> {
+        let mut range: Option<(&str, &str)> = None;
+        for months in self.tables.get("customer")?.values() {
+            for month in months.keys().filter(|m| m.as_str() != UNKNOWN_MONTH) {
+                range = Some(match range {
+                    Some((first, last)) => (first.min(month.as_str()), last.max(month.as_str())),
+                    None => (month.as_str(), month.as_str()),
+                });
+            }
+        }
+        range
+    }
+
+    /// 客户名称
+    pub fn customer_name(&self, code: &str) -> &str {
+        self.customer_names.get(code).map(|s| s.as_str()).unwrap_or_default()
+    }
+
+    /// 每个客户的全部月份汇总（用于排行）
+    pub fn customer_totals(&self) -> Vec<CustomerData> {
+        self.customer_totals_between(None, None)
+    }
+
+    /// 每个客户在月份区间内的汇总（含首尾），指定区间时不含未知月份
+    pub fn customer_totals_between(&self, start_month: Option<&str>, end_month: Option<&str>) -> Vec<CustomerData> {
+        let Some(table) = self.tables.get("customer") else {
+            return Vec::new();
+        };
+
+        table
+            .iter()
+            .filter_map(|(code, months)| {
+                let mut total = CubeCell::default();
+                let mut found = false;
+                for (month, cell) in months {
+                    if month_in_range(month, start_month, end_month) {
+                        total.add(cell);
+                        found = true;
+                    }
+                }
+                if !found {
+                    return None;
+                }
+
+                Some(CustomerData {
+                    customer_code: code.clone(),
+                    customer_name: self.customer_name(code).to_string(),
+                    pay_amount: total.pay_amount,
+                    recharge_deduction: total.recharge_deduction,
+                    total_amount: total.total_amount,
+                    order_count: total.order_count,
+                    customer_nature: None,
+                    sales_rep: None,
+                })
+            })
+            .collect()
+    }
+
+    /// 估算内存占用（字节）
+    pub fn estimated_size(&self) -> usize {
+        let cells: usize = self
+            .tables
+            .values()
+            .flat_map(|table| table.iter())
+            .map(|(value, months)| {
+                value.capacity()
+                    + std::mem::size_of::<String>() * 2
+                    + months
+                        .keys()
+                        .map(|m| m.capacity() + std::mem::size_of::<(String, CubeCell)>())
+                        .sum::<usize>()
+            })
+            .sum();
+
+        let names: usize = self
+            .customer_names
+            .iter()
+            .map(|(code, name)| code.capacity() + name.capacity() + std::mem::size_of::<String>() * 2)
+            .sum();
+
+        cells + names
+    }
+}
+
+/// 月份是否在区间内（"YYYY-MM" 可直接按字符串比较）
+pub fn month_in_range(month: &str, start_month: Option<&str>, end_month: Option<&str>) -> bool {
+    if start_month.is_none() && end_month.is_none() {
+        return true;
+    }
+    if month == UNKNOWN_MONTH {
+        return false;
+    }
+    start_month.is_none_or(|start| month >= start) && end_month.is_none_or(|end| month <= end)
+}
+
+/// 聚合单个维度
+fn build_table(store: &ColumnarStore, dim: &str) -> DimensionTable {
+    let Some((column, dict)) = store.dimension(dim) else {
+        return DimensionTable::new();
+    };
+
+    let mut cells: HashMap<(u32, u32), CubeCell> = HashMap::new();
+    for (i, &id) in column.iter().enumerate() {
+        if id == NONE_ID {
+            continue;
+        }
+        cells
+            .entry((id, store.month[i]))
+            .or_default()
+            .add_row(store.pay_amount[i], store.recharge_deduction[i], store.total_amount(i));
+    }
+
+    let mut table = DimensionTable::with_capacity(dict.len());
+    for ((id, month_id), cell) in cells {
+        let value = dict.get(id).unwrap_or_default().to_string();
+        let month = store.months.get(month_id).unwrap_or(UNKNOWN_MONTH).to_string();
+        table.entry(value).or_default().insert(month, cell);
+    }
+    table
+}
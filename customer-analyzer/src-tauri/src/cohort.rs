@@ -0,0 +1,171 @@
+use crate::columnar_store::ColumnarStore;
+use crate::customer_master::CustomerMaster;
+use crate::excel_processor::percent_of;
+use crate::money::Money;
+use crate::period::{Period, TrendOptions};
+use crate::row_filter::RowFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 留存分析参数
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CohortOptions {
+    // 同期群的周期长度（按日、周、月、季、年）及财年起始月份
+    pub trend: TrendOptions,
+    // 每个同期群最多统计的后续周期数（0 为不限）
+    pub max_periods: usize,
+}
+
+/// 同期群在首购后第 offset 个周期的留存情况
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CohortCell {
+    // 0 为首购周期
+    pub offset: usize,
+    pub period: String,
+    pub retained_customers: usize,
+    // 留存客户数 / 同期群客户数（百分比）
+    pub retention_rate: f64,
+    pub total_amount: Money,
+    // 该周期金额 / 首购周期金额（百分比）
+    pub revenue_retention: f64,
+}
+
+/// 一个同期群：首购周期相同的客户
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CohortRow {
+    pub cohort: String,
+    pub customer_count: usize,
+    pub total_amount: Money,
+    // 从首购周期到数据最后一个周期（受 max_periods 限制）
+    pub cells: Vec<CohortCell>,
+}
+
+/// 留存分析结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CohortResult {
+    pub options: CohortOptions,
+    pub cohorts: Vec<CohortRow>,
+    // 各偏移周期的平均留存率（按同期群客户数加权，只计入已经历该周期的同期群）
+    pub average_retention: Vec<f64>,
+    pub average_revenue_retention: Vec<f64>,
+    pub customer_count: usize,
+    // 没有日期（或月份）的行无法归入同期群，只统计数量
+    pub unknown_period_rows: usize,
+    pub process_time_ms: u128,
+}
+
+/// 按首购周期划分同期群，统计之后每个周期的留存客户数、留存率和金额留存
+///
+/// 首购周期以筛选后的数据为准（例如只看某个省份时，为该省份内的首次购买）。
+/// 有客户主数据时按标准客户合并编码。
+pub fn analyze(
+    store: &ColumnarStore,
+    options: &CohortOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<CohortResult, String> {
+    let start_time = std::time::Instant::now();
+    let trend = &options.trend;
+    trend.validate()?;
+
+    let compiled = filter.filter(|f| !f.is_empty()).map(|f| f.compile(store));
+    let customer_ids: Option<Vec<u32>> = master.map(|master| master.canonical_ids(store.customer_codes.values()));
+
+    // 客户 → 周期 → 金额
+    let mut purchases: HashMap<u32, BTreeMap<Period, Money>> = HashMap::new();
+    let mut unknown_period_rows = 0;
+    for i in 0..store.len() {
+        if compiled.as_ref().is_some_and(|f| !f.matches(i)) {
+            continue;
+        }
+        let Some(period) = trend.period_of_row(store, i) else {
+            unknown_period_rows += 1;
+            continue;
+        };
+        let customer = match &customer_ids {
+            Some(ids) => ids[store.customer[i] as usize],
+            None => store.customer[i],
+        };
+        *purchases.entry(customer).or_default().entry(period).or_default() += store.total_amount(i);
+    }
+
+    let first = purchases.values().filter_map(|periods| periods.keys().next()).min().copied();
+    let last = purchases.values().filter_map(|periods| periods.keys().next_back()).max().copied();
+    let axis = match (first, last) {
+        (Some(first), Some(last)) => trend.axis(first, last),
+        _ => Vec::new(),
+    };
+    let position: HashMap<Period, usize> = axis.iter().enumerate().map(|(idx, p)| (*p, idx)).collect();
+
+    // 同期群（首购周期位置）→ 各偏移周期的 (客户数, 金额)
+    let mut cohorts: BTreeMap<usize, Vec<(usize, Money)>> = BTreeMap::new();
+    for periods in purchases.values() {
+        let Some(first) = periods.keys().next().and_then(|p| position.get(p)).copied() else {
+            continue;
+        };
+        let cells = cohorts
+            .entry(first)
+            .or_insert_with(|| vec![(0, Money::ZERO); axis.len() - first]);
+        for (period, amount) in periods {
+            let offset = position[period] - first;
+            cells[offset].0 += 1;
+            cells[offset].1 += *amount;
+        }
+    }
+
+    let limit = if options.max_periods == 0 { usize::MAX } else { options.max_periods + 1 };
+    let cohorts: Vec<CohortRow> = cohorts
+        .into_iter()
+        .map(|(first, cells)| {
+            let (customer_count, first_amount) = cells[0];
+            let cells: Vec<CohortCell> = cells
+                .into_iter()
+                .take(limit)
+                .enumerate()
+                .map(|(offset, (retained_customers, total_amount))| CohortCell {
+                    offset,
+                    period: trend.label(axis[first + offset]),
+                    retained_customers,
+                    retention_rate: percent_of(retained_customers as f64, customer_count as f64),
+                    total_amount,
+                    revenue_retention: percent_of(total_amount.to_yuan(), first_amount.to_yuan()),
+                })
+                .collect();
+
+            CohortRow {
+                cohort: trend.label(axis[first]),
+                customer_count,
+                total_amount: cells.iter().map(|cell| cell.total_amount).sum(),
+                cells,
+            }
+        })
+        .collect();
+
+    // 平均留存：每个偏移周期按同期群客户数（金额留存按首购金额）加权
+    let max_offset = cohorts.iter().map(|row| row.cells.len()).max().unwrap_or(0);
+    let mut average_retention = Vec::with_capacity(max_offset);
+    let mut average_revenue_retention = Vec::with_capacity(max_offset);
+    for offset in 0..max_offset {
+        let (mut retained, mut base_customers) = (0usize, 0usize);
+        let (mut amount, mut base_amount) = (Money::ZERO, Money::ZERO);
+        for row in cohorts.iter().filter(|row| offset < row.cells.len()) {
+            retained += row.cells[offset].retained_customers;
+            base_customers += row.customer_count;
+            amount += row.cells[offset].total_amount;
+            base_amount += row.cells[0].total_amount;
+        }
+        average_retention.push(percent_of(retained as f64, base_customers as f64));
+        average_revenue_retention.push(percent_of(amount.to_yuan(), base_amount.to_yuan()));
+    }
+
+    Ok(CohortResult {
+        options: options.clone(),
+        customer_count: purchases.len(),
+        cohorts,
+        average_retention,
+        average_revenue_retention,
+        unknown_period_rows,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
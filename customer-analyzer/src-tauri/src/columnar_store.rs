@@ -0,0 +1,402 @@
+use crate::money::Money;
+use chrono::{Datelike, NaiveDate};
+use crate::monthly_analysis::{CachedRow, CUSTOM_DIMENSION_PREFIX};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 空值（None）对应的ID
+pub const NONE_ID: u32 = u32::MAX;
+
+/// 日期列中的空值
+pub const NO_DATE: i32 = i32::MIN;
+
+/// 字符串字典：相同的值只保存一份，行数据中只保存数值ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct Dictionary {
+    values: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl From<Vec<String>> for Dictionary {
+    fn from(values: Vec<String>) -> Self {
+        let index = values
+            .iter()
+            .enumerate()
+            .map(|(id, v)| (v.clone(), id as u32))
+            .collect();
+        Dictionary { values, index }
+    }
+}
+
+impl From<Dictionary> for Vec<String> {
+    fn from(dict: Dictionary) -> Self {
+        dict.values
+    }
+}
+
+impl Dictionary {
+    /// 取得值对应的ID，不存在时加入字典
+    pub fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&id) = self.index.get(value) {
+            return id;
+        }
+        let id = self.values.len() as u32;
+        self.values.push(value.to_string());
+        self.index.insert(value.to_string(), id);
+        id
+    }
+
+    /// 可选值的ID，None 或空字符串对应 NONE_ID
+    pub fn intern_opt(&mut self, value: Option<&str>) -> u32 {
+        match value {
+            Some(v) if !v.is_empty() => self.intern(v),
+            _ => NONE_ID,
+        }
+    }
+
+    /// 查找值对应的ID
+    pub fn lookup(&self, value: &str) -> Option<u32> {
+        self.index.get(value).copied()
+    }
+
+    /// 根据ID取值，NONE_ID 返回 None
+    pub fn get(&self, id: u32) -> Option<&str> {
+        if id == NONE_ID {
+            None
+        } else {
+            self.values.get(id as usize).map(|s| s.as_str())
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// 所有值（按ID顺序）
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    /// 排序后的所有值
+    pub fn sorted_values(&self) -> Vec<String> {
+        let mut values = self.values.clone();
+        values.sort();
+        values
+    }
+
+    /// 将另一个字典的值合并进来，返回旧ID到新ID的映射
+    fn merge_from(&mut self, other: &Dictionary) -> Vec<u32> {
+        other.values.iter().map(|v| self.intern(v)).collect()
+    }
+
+    fn estimated_size(&self) -> usize {
+        // 值本身保存两份（列表 + 索引），另加索引条目开销
+        self.values
+            .iter()
+            .map(|v| v.capacity() * 2 + std::mem::size_of::<String>() * 2 + std::mem::size_of::<u32>())
+            .sum()
+    }
+}
+
+fn remap(map: &[u32], id: u32) -> u32 {
+    if id == NONE_ID {
+        NONE_ID
+    } else {
+        map[id as usize]
+    }
+}
+
+/// 自定义维度列
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomColumn {
+    pub dict: Dictionary,
+    pub ids: Vec<u32>,
+}
+
+impl CustomColumn {
+    /// 新建一列，前 `rows` 行为空值
+    fn with_empty_rows(rows: usize) -> Self {
+        CustomColumn {
+            dict: Dictionary::default(),
+            ids: vec![NONE_ID; rows],
+        }
+    }
+}
+
+/// 列式存储的缓存数据
+///
+/// 维度列（客户、省市区、地区、月份）以字典ID保存，金额以连续数组保存，
+/// 大幅减少重复字符串带来的内存占用，并使按维度筛选变为整数比较。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnarStore {
+    // 字典
+    pub customer_codes: Dictionary,
+    pub customer_names: Dictionary,
+    pub provinces: Dictionary,
+    pub cities: Dictionary,
+    pub districts: Dictionary,
+    pub regions: Dictionary,
+    pub months: Dictionary,
+
+    // 列
+    pub customer: Vec<u32>,
+    pub customer_name: Vec<u32>,
+    pub province: Vec<u32>,
+    pub city: Vec<u32>,
+    pub district: Vec<u32>,
+    pub region: Vec<u32>,
+    pub month: Vec<u32>,
+    pub pay_amount: Vec<Money>,
+    pub recharge_deduction: Vec<Money>,
+    // 订单日期（公元纪年天数，没有日期为 NO_DATE），用于按日、按周分析
+    #[serde(default)]
+    pub date: Vec<i32>,
+
+    // 自定义维度列（列名 → 列）
+    #[serde(default)]
+    pub custom: BTreeMap<String, CustomColumn>,
+}
+
+impl ColumnarStore {
+    pub fn with_capacity(rows: usize) -> Self {
+        ColumnarStore {
+            customer: Vec::with_capacity(rows),
+            customer_name: Vec::with_capacity(rows),
+            province: Vec::with_capacity(rows),
+            city: Vec::with_capacity(rows),
+            district: Vec::with_capacity(rows),
+            region: Vec::with_capacity(rows),
+            month: Vec::with_capacity(rows),
+            pay_amount: Vec::with_capacity(rows),
+            recharge_deduction: Vec::with_capacity(rows),
+            date: Vec::with_capacity(rows),
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.customer.len()
+    }
+
+    /// 追加一行
+    pub fn push(&mut self, row: &CachedRow) {
+        let rows_before = self.len();
+        let customer = self.customer_codes.intern(&row.customer_code);
+        let customer_name = self.customer_names.intern_opt(Some(&row.customer_name));
+        let province = self.provinces.intern_opt(row.province.as_deref());
+        let city = self.cities.intern_opt(row.city.as_deref());
+        let district = self.districts.intern_opt(row.district.as_deref());
+        let region = self.regions.intern_opt(row.region.as_deref());
+        let month = self.months.intern_opt(row.month.as_deref());
+
+        self.customer.push(customer);
+        self.customer_name.push(customer_name);
+        self.province.push(province);
+        self.city.push(city);
+        self.district.push(district);
+        self.region.push(region);
+        self.month.push(month);
+        self.pay_amount.push(row.pay_amount);
+        self.recharge_deduction.push(row.recharge_deduction);
+        self.date.push(
+            row.date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .map_or(NO_DATE, |d| d.num_days_from_ce()),
+        );
+
+        for (name, column) in self.custom.iter_mut() {
+            let id = column.dict.intern_opt(row.custom.get(name).map(|v| v.as_str()));
+            column.ids.push(id);
+        }
+        for (name, value) in &row.custom {
+            if !self.custom.contains_key(name) {
+                let mut column = CustomColumn::with_empty_rows(rows_before);
+                let id = column.dict.intern_opt(Some(value));
+                column.ids.push(id);
+                self.custom.insert(name.clone(), column);
+            }
+        }
+    }
+
+    /// 追加另一个存储的所有行（重新映射字典ID）
+    pub fn append(&mut self, other: &ColumnarStore) {
+        let rows_before = self.len();
+        let customer_map = self.customer_codes.merge_from(&other.customer_codes);
+        let name_map = self.customer_names.merge_from(&other.customer_names);
+        let province_map = self.provinces.merge_from(&other.provinces);
+        let city_map = self.cities.merge_from(&other.cities);
+        let district_map = self.districts.merge_from(&other.districts);
+        let region_map = self.regions.merge_from(&other.regions);
+        let month_map = self.months.merge_from(&other.months);
+
+        self.customer.extend(other.customer.iter().map(|&id| remap(&customer_map, id)));
+        self.customer_name.extend(other.customer_name.iter().map(|&id| remap(&name_map, id)));
+        self.province.extend(other.province.iter().map(|&id| remap(&province_map, id)));
+        self.city.extend(other.city.iter().map(|&id| remap(&city_map, id)));
+        self.district.extend(other.district.iter().map(|&id| remap(&district_map, id)));
+        self.region.extend(other.region.iter().map(|&id| remap(&region_map, id)));
+        self.month.extend(other.month.iter().map(|&id| remap(&month_map, id)));
+        self.pay_amount.extend_from_slice(&other.pay_amount);
+        self.recharge_deduction.extend_from_slice(&other.recharge_deduction);
+        self.date.resize(rows_before, NO_DATE);
+        self.date.extend_from_slice(&other.date);
+        self.date.resize(self.len(), NO_DATE);
+
+        // 自定义维度：任意一方缺少的列以空值补齐
+        for (name, other_column) in &other.custom {
+            let column = self
+                .custom
+                .entry(name.clone())
+                .or_insert_with(|| CustomColumn::with_empty_rows(rows_before));
+            let map = column.dict.merge_from(&other_column.dict);
+            column.ids.extend(other_column.ids.iter().map(|&id| remap(&map, id)));
+        }
+        let rows_after = self.len();
+        for column in self.custom.values_mut() {
+            column.ids.resize(rows_after, NONE_ID);
+        }
+    }
+
+    /// 第 i 行的总金额（支付金额 + 充值抵扣）
+    #[inline]
+    pub fn total_amount(&self, i: usize) -> Money {
+        self.pay_amount[i] + self.recharge_deduction[i]
+    }
+
+    pub fn customer_code(&self, i: usize) -> &str {
+        self.customer_codes.get(self.customer[i]).unwrap_or_default()
+    }
+
+    pub fn customer_name(&self, i: usize) -> &str {
+        self.customer_names.get(self.customer_name[i]).unwrap_or_default()
+    }
+
+    pub fn month(&self, i: usize) -> Option<&str> {
+        self.months.get(self.month[i])
+    }
+
+    /// 第 i 行的订单日期
+    pub fn date(&self, i: usize) -> Option<NaiveDate> {
+        self.date
+            .get(i)
+            .filter(|&&days| days != NO_DATE)
+            .and_then(|&days| NaiveDate::from_num_days_from_ce_opt(days))
+    }
+
+    /// 是否包含日期列（旧版缓存没有）
+    pub fn has_date_column(&self) -> bool {
+        self.date.len() == self.len()
+    }
+
+    /// 数据中最早和最晚的日期
+    pub fn date_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let mut days = self.date.iter().copied().filter(|&d| d != NO_DATE);
+        let first = days.next()?;
+        let (min, max) = days.fold((first, first), |(min, max), d| (min.min(d), max.max(d)));
+        Some((
+            NaiveDate::from_num_days_from_ce_opt(min)?,
+            NaiveDate::from_num_days_from_ce_opt(max)?,
+        ))
+    }
+
+    /// 所有可分析的维度（固定维度 + "custom:列名"）
+    pub fn dimension_names(&self) -> Vec<String> {
+        ["customer", "province", "city", "district", "region"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(self.custom.keys().map(|name| format!("{}{}", CUSTOM_DIMENSION_PREFIX, name)))
+            .collect()
+    }
+
+    /// 自定义维度的可选值（列名 → 排序后的值）
+    pub fn custom_dimension_values(&self) -> BTreeMap<String, Vec<String>> {
+        self.custom
+            .iter()
+            .map(|(name, column)| (name.clone(), column.dict.sorted_values()))
+            .collect()
+    }
+
+    /// 维度列及其字典（customer / province / city / district / region / custom:列名）
+    pub fn dimension(&self, analysis_type: &str) -> Option<(&[u32], &Dictionary)> {
+        if let Some(name) = analysis_type.strip_prefix(CUSTOM_DIMENSION_PREFIX) {
+            return self.custom.get(name).map(|column| (column.ids.as_slice(), &column.dict));
+        }
+        match analysis_type {
+            "customer" => Some((&self.customer, &self.customer_codes)),
+            "province" => Some((&self.province, &self.provinces)),
+            "city" => Some((&self.city, &self.cities)),
+            "district" => Some((&self.district, &self.districts)),
+            "region" => Some((&self.region, &self.regions)),
+            _ => None,
+        }
+    }
+
+    /// 指定维度等于目标值的行号
+    pub fn matching_rows(&self, analysis_type: &str, target: &str) -> Vec<usize> {
+        let Some((column, dict)) = self.dimension(analysis_type) else {
+            return Vec::new();
+        };
+        let Some(target_id) = dict.lookup(target) else {
+            return Vec::new();
+        };
+
+        column
+            .iter()
+            .enumerate()
+            .filter(|(_, &id)| id == target_id)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// 还原第 i 行（用于导出订单明细）
+    pub fn row(&self, i: usize) -> CachedRow {
+        CachedRow {
+            customer_code: self.customer_code(i).to_string(),
+            customer_name: self.customer_name(i).to_string(),
+            pay_amount: self.pay_amount[i],
+            recharge_deduction: self.recharge_deduction[i],
+            total_amount: self.total_amount(i),
+            province: self.provinces.get(self.province[i]).map(str::to_string),
+            city: self.cities.get(self.city[i]).map(str::to_string),
+            district: self.districts.get(self.district[i]).map(str::to_string),
+            region: self.regions.get(self.region[i]).map(str::to_string),
+            month: self.month(i).map(str::to_string),
+            date: self.date(i).map(|d| d.format("%Y-%m-%d").to_string()),
+            custom: self
+                .custom
+                .iter()
+                .filter_map(|(name, column)| {
+                    column.dict.get(column.ids[i]).map(|v| (name.clone(), v.to_string()))
+                })
+                .collect(),
+        }
+    }
+
+    /// 估算内存占用（字节）
+    pub fn estimated_size(&self) -> usize {
+        let columns = self.len()
+            * (std::mem::size_of::<u32>() * 7 + std::mem::size_of::<Money>() * 2 + std::mem::size_of::<i32>());
+
+        let dictionaries = self.customer_codes.estimated_size()
+            + self.customer_names.estimated_size()
+            + self.provinces.estimated_size()
+            + self.cities.estimated_size()
+            + self.districts.estimated_size()
+            + self.regions.estimated_size()
+            + self.months.estimated_size();
+
+        let custom: usize = self
+            .custom
+            .values()
+            .map(|column| column.ids.len() * std::mem::size_of::<u32>() + column.dict.estimated_size())
+            .sum();
+
+        columns + dictionaries + custom
+    }
+}
@@ -0,0 +1,183 @@
+use crate::aggregate_cube::{AggregateCube, CubeCell};
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::excel_processor::RankMetric;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 客户集中度指标
+///
+/// 只统计指标值大于0的客户（退款导致合计为负的客户不参与计算）。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConcentrationMetrics {
+    pub customer_count: usize,
+    pub metric_total: f64,
+    // 前5/10/20名客户占比（%）
+    pub cr5: f64,
+    pub cr10: f64,
+    pub cr20: f64,
+    // 赫芬达尔-赫希曼指数（份额按百分比计，范围 0 ~ 10000）
+    pub hhi: f64,
+    // 基尼系数（0 为完全平均，越接近 1 越集中）
+    pub gini: f64,
+}
+
+/// 某个分组（省份、地区或月份）的集中度
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupConcentration {
+    pub group: String,
+    #[serde(flatten)]
+    pub metrics: ConcentrationMetrics,
+}
+
+/// 集中度分析结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConcentrationResult {
+    pub metric: RankMetric,
+    pub overall: ConcentrationMetrics,
+    pub by_province: Vec<GroupConcentration>,
+    pub by_region: Vec<GroupConcentration>,
+    pub by_month: Vec<GroupConcentration>,
+    pub process_time_ms: u128,
+}
+
+/// 由各客户的指标值计算集中度
+pub fn compute(values: impl IntoIterator<Item = f64>) -> ConcentrationMetrics {
+    let mut values: Vec<f64> = values.into_iter().filter(|v| *v > 0.0).collect();
+    if values.is_empty() {
+        return ConcentrationMetrics::default();
+    }
+
+    values.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let n = values.len();
+    let total: f64 = values.iter().sum();
+    let top_share = |k: usize| values.iter().take(k).sum::<f64>() / total * 100.0;
+
+    let hhi: f64 = values.iter().map(|v| (v / total * 100.0).powi(2)).sum();
+
+    // 升序排列后 G = 2Σ(i·x_i) / (nΣx) - (n+1)/n，i 从 1 开始
+    let weighted: f64 = values
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, v)| (i + 1) as f64 * v)
+        .sum();
+    let gini = 2.0 * weighted / (n as f64 * total) - (n as f64 + 1.0) / n as f64;
+
+    ConcentrationMetrics {
+        customer_count: n,
+        metric_total: total,
+        cr5: top_share(5),
+        cr10: top_share(10),
+        cr20: top_share(20),
+        hhi,
+        gini,
+    }
+}
+
+/// 按维度分组计算集中度（需要客户 × 维度的交叉汇总，因此基于行数据）
+///
+/// customer_ids 为客户编码ID → 标准客户序号（有客户主数据时）。
+fn by_dimension(
+    store: &ColumnarStore,
+    analysis_type: &str,
+    metric: RankMetric,
+    customer_ids: Option<&[u32]>,
+) -> Vec<GroupConcentration> {
+    let Some((column, dict)) = store.dimension(analysis_type) else {
+        return Vec::new();
+    };
+
+    let mut sums: HashMap<(u32, u32), CubeCell> = HashMap::new();
+    for (i, &group_id) in column.iter().enumerate() {
+        if group_id == NONE_ID {
+            continue;
+        }
+        let customer = match customer_ids {
+            Some(ids) => ids[store.customer[i] as usize],
+            None => store.customer[i],
+        };
+        sums.entry((group_id, customer)).or_default().add_row(
+            store.pay_amount[i],
+            store.recharge_deduction[i],
+            store.total_amount(i),
+        );
+    }
+
+    let mut groups: HashMap<u32, Vec<f64>> = HashMap::new();
+    for ((group_id, _), cell) in sums {
+        groups.entry(group_id).or_default().push(cell.metric_value(metric));
+    }
+
+    let mut result: Vec<GroupConcentration> = groups
+        .into_iter()
+        .map(|(group_id, values)| GroupConcentration {
+            group: dict.get(group_id).unwrap_or_default().to_string(),
+            metrics: compute(values),
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.metrics
+            .metric_total
+            .partial_cmp(&a.metrics.metric_total)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    result
+}
+
+/// 按月份计算集中度（基于立方体中的客户 × 月份汇总，有客户主数据时按标准客户合并）
+fn by_month(cube: &AggregateCube, metric: RankMetric, master: Option<&CustomerMaster>) -> Vec<GroupConcentration> {
+    let mut months: BTreeMap<&str, HashMap<&str, CubeCell>> = BTreeMap::new();
+    if let Some(table) = cube.tables.get("customer") {
+        for (code, customer_months) in table {
+            let customer = master.map_or(code.as_str(), |master| master.canonical_code(code));
+            for (month, cell) in customer_months {
+                months
+                    .entry(month.as_str())
+                    .or_default()
+                    .entry(customer)
+                    .or_default()
+                    .add(cell);
+            }
+        }
+    }
+
+    months
+        .into_iter()
+        .map(|(month, customers)| GroupConcentration {
+            group: month.to_string(),
+            metrics: compute(customers.values().map(|cell| cell.metric_value(metric))),
+        })
+        .collect()
+}
+
+/// 客户集中度分析：整体、按省份、按地区、按月份（有客户主数据时按标准客户合并编码）
+pub fn analyze(
+    cube: &AggregateCube,
+    store: &ColumnarStore,
+    metric: RankMetric,
+    master: Option<&CustomerMaster>,
+) -> ConcentrationResult {
+    let start_time = std::time::Instant::now();
+
+    let mut customers = cube.customer_totals();
+    if let Some(master) = master {
+        customers = master.merge_customers(customers);
+    }
+    let overall = compute(customers.iter().map(|c| metric.value_of(c)));
+
+    let customer_ids: Option<Vec<u32>> = master.map(|master| master.canonical_ids(store.customer_codes.values()));
+    let (by_province, by_region) = rayon::join(
+        || by_dimension(store, "province", metric, customer_ids.as_deref()),
+        || by_dimension(store, "region", metric, customer_ids.as_deref()),
+    );
+
+    ConcentrationResult {
+        metric,
+        overall,
+        by_province,
+        by_region,
+        by_month: by_month(cube, metric, master),
+        process_time_ms: start_time.elapsed().as_millis(),
+    }
+}
@@ -0,0 +1,319 @@
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::money::Money;
+use crate::period::{Period, TrendOptions};
+use crate::row_filter::RowFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 客户生命周期分类
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleCategory {
+    // 首次购买
+    New,
+    // 上一周期也有购买
+    Returning,
+    // 中断至少一个周期后重新购买
+    Reactivated,
+    // 连续 lost_after_periods 个周期没有购买
+    Lost,
+}
+
+/// 客户生命周期分析参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LifecycleOptions {
+    pub trend: TrendOptions,
+    // 连续多少个周期没有购买视为流失
+    pub lost_after_periods: usize,
+}
+
+impl Default for LifecycleOptions {
+    fn default() -> Self {
+        LifecycleOptions {
+            trend: TrendOptions::default(),
+            lost_after_periods: 3,
+        }
+    }
+}
+
+/// 某一分类的客户数和金额
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CategoryStats {
+    pub customer_count: usize,
+    pub total_amount: Money,
+}
+
+/// 一个周期的客户构成
+///
+/// 新客户、回头客户、召回客户之和即当期有购买的客户；流失客户的金额为其最后一个购买周期的金额。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LifecyclePeriod {
+    pub period: String,
+    pub active_customers: usize,
+    pub total_amount: Money,
+    pub new: CategoryStats,
+    pub returning: CategoryStats,
+    pub reactivated: CategoryStats,
+    pub lost: CategoryStats,
+}
+
+/// 客户生命周期分析结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LifecycleResult {
+    pub options: LifecycleOptions,
+    pub periods: Vec<LifecyclePeriod>,
+    pub customer_count: usize,
+    // 没有日期（或月份）的行无法归入周期，只统计数量
+    pub unknown_period_rows: usize,
+    pub process_time_ms: u128,
+}
+
+/// 某周期某分类下的一个客户
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LifecycleCustomer {
+    pub customer_code: String,
+    pub customer_name: String,
+    pub category: LifecycleCategory,
+    // 当期金额（流失客户为最后一个购买周期的金额）
+    pub total_amount: Money,
+    pub first_period: String,
+    // 此前最后一个购买周期（新客户为 None）
+    pub previous_period: Option<String>,
+}
+
+/// 一个客户（有主数据时为标准客户）的购买记录
+struct CustomerHistory {
+    // 首次出现的客户编码ID和名称ID
+    customer: u32,
+    name: u32,
+    // 周期序号 → 金额
+    periods: BTreeMap<usize, Money>,
+}
+
+/// 一次分类：周期序号、分类、客户下标、金额、此前最后一个购买周期
+struct Event {
+    position: usize,
+    category: LifecycleCategory,
+    customer: usize,
+    amount: Money,
+    previous: Option<usize>,
+}
+
+struct Histories {
+    axis: Vec<Period>,
+    customers: Vec<CustomerHistory>,
+    unknown_period_rows: usize,
+}
+
+fn collect(
+    store: &ColumnarStore,
+    options: &LifecycleOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<Histories, String> {
+    options.trend.validate()?;
+    if options.lost_after_periods == 0 {
+        return Err("流失判定周期数必须大于0".to_string());
+    }
+    let trend = &options.trend;
+
+    let compiled = filter.filter(|f| !f.is_empty()).map(|f| f.compile(store));
+    let customer_ids: Option<Vec<u32>> = master.map(|master| master.canonical_ids(store.customer_codes.values()));
+
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    let mut customers: Vec<(u32, u32, BTreeMap<Period, Money>)> = Vec::new();
+    let mut unknown_period_rows = 0;
+    for i in 0..store.len() {
+        if compiled.as_ref().is_some_and(|f| !f.matches(i)) {
+            continue;
+        }
+        let Some(period) = trend.period_of_row(store, i) else {
+            unknown_period_rows += 1;
+            continue;
+        };
+        let key = match &customer_ids {
+            Some(ids) => ids[store.customer[i] as usize],
+            None => store.customer[i],
+        };
+        let idx = *index.entry(key).or_insert_with(|| {
+            customers.push((store.customer[i], NONE_ID, BTreeMap::new()));
+            customers.len() - 1
+        });
+        let entry = &mut customers[idx];
+        if entry.1 == NONE_ID {
+            entry.1 = store.customer_name[i];
+        }
+        *entry.2.entry(period).or_default() += store.total_amount(i);
+    }
+
+    let first = customers.iter().filter_map(|(_, _, periods)| periods.keys().next()).min().copied();
+    let last = customers.iter().filter_map(|(_, _, periods)| periods.keys().next_back()).max().copied();
+    let axis = match (first, last) {
+        (Some(first), Some(last)) => trend.axis(first, last),
+        _ => Vec::new(),
+    };
+    let position: HashMap<Period, usize> = axis.iter().enumerate().map(|(idx, p)| (*p, idx)).collect();
+
+    let customers = customers
+        .into_iter()
+        .map(|(customer, name, periods)| CustomerHistory {
+            customer,
+            name,
+            periods: periods.into_iter().map(|(p, amount)| (position[&p], amount)).collect(),
+        })
+        .collect();
+
+    Ok(Histories {
+        axis,
+        customers,
+        unknown_period_rows,
+    })
+}
+
+/// 为每个客户的每次购买和每次流失生成一条分类
+fn classify(histories: &Histories, lost_after: usize) -> Vec<Event> {
+    let mut events = Vec::new();
+    for (customer, history) in histories.customers.iter().enumerate() {
+        let mut previous: Option<(usize, Money)> = None;
+        for (&position, &amount) in &history.periods {
+            let category = match previous {
+                None => LifecycleCategory::New,
+                Some((prev, _)) if prev + 1 == position => LifecycleCategory::Returning,
+                Some(_) => LifecycleCategory::Reactivated,
+            };
+            // 与上次购买间隔超过流失周期数：在中间判定为流失
+            if let Some((prev, prev_amount)) = previous {
+                if position - prev > lost_after {
+                    events.push(Event {
+                        position: prev + lost_after,
+                        category: LifecycleCategory::Lost,
+                        customer,
+                        amount: prev_amount,
+                        previous: Some(prev),
+                    });
+                }
+            }
+            events.push(Event {
+                position,
+                category,
+                customer,
+                amount,
+                previous: previous.map(|(prev, _)| prev),
+            });
+            previous = Some((position, amount));
+        }
+
+        // 最后一次购买后已经过了流失周期数
+        if let Some((prev, prev_amount)) = previous {
+            if prev + lost_after < histories.axis.len() {
+                events.push(Event {
+                    position: prev + lost_after,
+                    category: LifecycleCategory::Lost,
+                    customer,
+                    amount: prev_amount,
+                    previous: Some(prev),
+                });
+            }
+        }
+    }
+    events
+}
+
+/// 按周期统计新客户、回头客户、召回客户和流失客户的数量及金额
+pub fn analyze(
+    store: &ColumnarStore,
+    options: &LifecycleOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<LifecycleResult, String> {
+    let start_time = std::time::Instant::now();
+    let histories = collect(store, options, filter, master)?;
+
+    let mut periods: Vec<LifecyclePeriod> = histories
+        .axis
+        .iter()
+        .map(|period| LifecyclePeriod {
+            period: options.trend.label(*period),
+            active_customers: 0,
+            total_amount: Money::ZERO,
+            new: CategoryStats::default(),
+            returning: CategoryStats::default(),
+            reactivated: CategoryStats::default(),
+            lost: CategoryStats::default(),
+        })
+        .collect();
+
+    for event in classify(&histories, options.lost_after_periods) {
+        let period = &mut periods[event.position];
+        let stats = match event.category {
+            LifecycleCategory::New => &mut period.new,
+            LifecycleCategory::Returning => &mut period.returning,
+            LifecycleCategory::Reactivated => &mut period.reactivated,
+            LifecycleCategory::Lost => &mut period.lost,
+        };
+        stats.customer_count += 1;
+        stats.total_amount += event.amount;
+        if event.category != LifecycleCategory::Lost {
+            period.active_customers += 1;
+            period.total_amount += event.amount;
+        }
+    }
+
+    Ok(LifecycleResult {
+        options: options.clone(),
+        periods,
+        customer_count: histories.customers.len(),
+        unknown_period_rows: histories.unknown_period_rows,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
+
+/// 列出某周期某分类下的客户（按金额从高到低）
+pub fn customers(
+    store: &ColumnarStore,
+    options: &LifecycleOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+    period: &str,
+    category: LifecycleCategory,
+) -> Result<Vec<LifecycleCustomer>, String> {
+    let histories = collect(store, options, filter, master)?;
+    let trend = &options.trend;
+    let position = histories
+        .axis
+        .iter()
+        .position(|p| trend.label(*p) == period)
+        .ok_or_else(|| format!("未找到周期: {}", period))?;
+
+    let mut customers: Vec<LifecycleCustomer> = classify(&histories, options.lost_after_periods)
+        .into_iter()
+        .filter(|event| event.position == position && event.category == category)
+        .map(|event| {
+            let history = &histories.customers[event.customer];
+            let code = store.customer_codes.get(history.customer).unwrap_or_default();
+            let name = master
+                .and_then(|master| master.canonical_name(code))
+                .or_else(|| store.customer_names.get(history.name))
+                .unwrap_or_default();
+            let first = history.periods.keys().next().copied().unwrap_or(event.position);
+
+            LifecycleCustomer {
+                customer_code: master.map_or(code, |master| master.canonical_code(code)).to_string(),
+                customer_name: name.to_string(),
+                category,
+                total_amount: event.amount,
+                first_period: trend.label(histories.axis[first]),
+                previous_period: event.previous.map(|prev| trend.label(histories.axis[prev])),
+            }
+        })
+        .collect();
+    customers.sort_by(|a, b| {
+        b.total_amount
+            .cmp(&a.total_amount)
+            .then_with(|| a.customer_code.cmp(&b.customer_code))
+    });
+
+    Ok(customers)
+}
@@ -0,0 +1,294 @@
+use crate::data_quality::CodeNormalizationRules;
+use crate::excel_processor::CustomerData;
+use calamine::{open_workbook, Data, Reader, Xls, Xlsx};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 客户主数据中的一行：原始编码 → 标准客户
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MasterEntry {
+    pub code: String,
+    pub canonical_code: String,
+    pub canonical_name: String,
+    pub customer_nature: String,
+    pub sales_rep: String,
+}
+
+/// 持久化的客户主数据文件内容
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomerMasterFile {
+    pub file_path: String,
+    pub imported_at: String,
+    pub entries: Vec<MasterEntry>,
+}
+
+/// 标准客户的信息
+#[derive(Debug, Clone, Default)]
+struct CanonicalInfo {
+    name: String,
+    customer_nature: String,
+    sales_rep: String,
+    // 映射到该客户的所有原始编码（含标准编码本身）
+    codes: Vec<String>,
+}
+
+/// 客户主数据：将分支机构、重新注册等多个编码合并为一个标准客户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CustomerMasterFile", into = "CustomerMasterFile")]
+pub struct CustomerMaster {
+    file: CustomerMasterFile,
+    // 原始编码 → 标准编码
+    canonical_codes: HashMap<String, String>,
+    canonical: HashMap<String, CanonicalInfo>,
+}
+
+impl From<CustomerMasterFile> for CustomerMaster {
+    fn from(file: CustomerMasterFile) -> Self {
+        let mut canonical_codes = HashMap::new();
+        let mut canonical: HashMap<String, CanonicalInfo> = HashMap::new();
+
+        for entry in &file.entries {
+            canonical_codes.insert(entry.code.clone(), entry.canonical_code.clone());
+
+            let info = canonical.entry(entry.canonical_code.clone()).or_default();
+            for (field, value) in [
+                (&mut info.name, &entry.canonical_name),
+                (&mut info.customer_nature, &entry.customer_nature),
+                (&mut info.sales_rep, &entry.sales_rep),
+            ] {
+                if field.is_empty() && !value.is_empty() {
+                    field.clone_from(value);
+                }
+            }
+            if !info.codes.contains(&entry.code) {
+                info.codes.push(entry.code.clone());
+            }
+        }
+
+        for (canonical_code, info) in canonical.iter_mut() {
+            if !info.codes.contains(canonical_code) {
+                info.codes.push(canonical_code.clone());
+            }
+        }
+
+        CustomerMaster {
+            file,
+            canonical_codes,
+            canonical,
+        }
+    }
+}
+
+impl From<CustomerMaster> for CustomerMasterFile {
+    fn from(master: CustomerMaster) -> Self {
+        master.file
+    }
+}
+
+/// 客户主数据概况
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomerMasterInfo {
+    pub file_path: String,
+    pub imported_at: String,
+    pub entry_count: usize,
+    pub canonical_count: usize,
+}
+
+impl CustomerMaster {
+    pub fn info(&self) -> CustomerMasterInfo {
+        CustomerMasterInfo {
+            file_path: self.file.file_path.clone(),
+            imported_at: self.file.imported_at.clone(),
+            entry_count: self.file.entries.len(),
+            canonical_count: self.canonical.len(),
+        }
+    }
+
+    /// 编码是否在主数据中（作为原始编码或标准编码）
+    pub fn contains(&self, code: &str) -> bool {
+        self.canonical_codes.contains_key(code) || self.canonical.contains_key(code)
+    }
+
+    /// 标准编码（未映射的编码原样返回）
+    pub fn canonical_code<'a>(&'a self, code: &'a str) -> &'a str {
+        self.canonical_codes.get(code).map(|c| c.as_str()).unwrap_or(code)
+    }
+
+    /// 标准客户名称（主数据中没有名称时为 None）
+    pub fn canonical_name(&self, code: &str) -> Option<&str> {
+        self.canonical
+            .get(self.canonical_code(code))
+            .map(|info| info.name.as_str())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// 与该编码属于同一标准客户的所有原始编码
+    pub fn codes_for<'a>(&'a self, code: &'a str) -> Vec<&'a str> {
+        match self.canonical.get(self.canonical_code(code)) {
+            Some(info) => info.codes.iter().map(|c| c.as_str()).collect(),
+            None => vec![code],
+        }
+    }
+
+    /// 按标准客户合并客户汇总，并补充标准名称、客户性质和业务员
+    pub fn merge_customers(&self, customers: Vec<CustomerData>) -> Vec<CustomerData> {
+        let mut merged: HashMap<String, CustomerData> = HashMap::with_capacity(customers.len());
+
+        for mut customer in customers {
+            let canonical_code = self.canonical_code(&customer.customer_code).to_string();
+            if let Some(info) = self.canonical.get(&canonical_code) {
+                if !info.name.is_empty() {
+                    customer.customer_name.clone_from(&info.name);
+                }
+                customer.customer_nature = Some(info.customer_nature.clone()).filter(|v| !v.is_empty());
+                customer.sales_rep = Some(info.sales_rep.clone()).filter(|v| !v.is_empty());
+            }
+            customer.customer_code.clone_from(&canonical_code);
+
+            merged
+                .entry(canonical_code)
+                .and_modify(|existing| {
+                    existing.pay_amount += customer.pay_amount;
+                    existing.recharge_deduction += customer.recharge_deduction;
+                    existing.total_amount += customer.total_amount;
+                    existing.order_count += customer.order_count;
+                    if existing.customer_name.is_empty() && !customer.customer_name.is_empty() {
+                        existing.customer_name.clone_from(&customer.customer_name);
+                    }
+                })
+                .or_insert(customer);
+        }
+
+        merged.into_values().collect()
+    }
+
+    /// 客户编码字典 → 标准客户序号（同一标准客户的多个编码得到相同序号）
+    pub fn canonical_ids(&self, codes: &[String]) -> Vec<u32> {
+        let mut canonical: HashMap<&str, u32> = HashMap::new();
+        codes
+            .iter()
+            .map(|code| {
+                let next = canonical.len() as u32;
+                *canonical.entry(self.canonical_code(code)).or_insert(next)
+            })
+            .collect()
+    }
+
+    /// 主数据中没有的客户（按总金额从高到低）
+    pub fn unmapped_customers(&self, customers: Vec<CustomerData>) -> Vec<CustomerData> {
+        let mut unmapped: Vec<CustomerData> = customers
+            .into_iter()
+            .filter(|c| !self.contains(&c.customer_code))
+            .collect();
+        unmapped.sort_by(|a, b| {
+            b.total_amount
+                .cmp(&a.total_amount)
+                .then_with(|| a.customer_code.cmp(&b.customer_code))
+        });
+        unmapped
+    }
+}
+
+fn cell_to_string(value: &Data) -> String {
+    match value {
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::String(s) => s.clone(),
+        Data::Bool(b) => b.to_string(),
+        Data::DateTimeIso(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+/// 读取客户主数据工作簿（第一个工作表）
+///
+/// 必需列：客户编码；可选列：标准编码、标准名称、客户性质、业务员。
+/// 未填写标准编码时视为该编码自身即为标准客户。
+pub fn load_master_workbook(
+    file_path: &str,
+    code_rules: &CodeNormalizationRules,
+) -> Result<CustomerMaster, String> {
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let rows: Vec<Vec<Data>> = match extension.as_str() {
+        "xlsx" => {
+            let mut workbook: Xlsx<_> = open_workbook(file_path)
+                .map_err(|e| format!("无法打开Excel文件: {}", e))?;
+            let sheet_name = workbook.sheet_names().first()
+                .ok_or("Excel文件没有工作表")?.clone();
+            let range = workbook.worksheet_range(&sheet_name)
+                .map_err(|e| format!("无法读取工作表: {}", e))?;
+            range.rows().map(|r| r.to_vec()).collect()
+        },
+        "xls" => {
+            let mut workbook: Xls<_> = open_workbook(file_path)
+                .map_err(|e| format!("无法打开Excel文件: {}", e))?;
+            let sheet_name = workbook.sheet_names().first()
+                .ok_or("Excel文件没有工作表")?.clone();
+            let range = workbook.worksheet_range(&sheet_name)
+                .map_err(|e| format!("无法读取工作表: {}", e))?;
+            range.rows().map(|r| r.to_vec()).collect()
+        },
+        _ => return Err(format!("不支持的文件格式: {}", extension)),
+    };
+
+    let header = rows.first().ok_or("Excel文件为空")?;
+    let mut code_idx = None;
+    let mut canonical_code_idx = None;
+    let mut canonical_name_idx = None;
+    let mut nature_idx = None;
+    let mut sales_rep_idx = None;
+
+    for (idx, cell) in header.iter().enumerate() {
+        match cell_to_string(cell).trim() {
+            "客户编码" | "原始编码" | "编码" => code_idx = Some(idx),
+            "标准编码" | "标准客户编码" | "主客户编码" | "合并编码" => canonical_code_idx = Some(idx),
+            "标准名称" | "标准客户名称" | "客户名称" => canonical_name_idx = Some(idx),
+            "客户性质" => nature_idx = Some(idx),
+            "业务员" | "销售代表" => sales_rep_idx = Some(idx),
+            _ => {}
+        }
+    }
+    let code_idx = code_idx.ok_or("缺少必需列: 客户编码")?;
+
+    let text = |row: &[Data], idx: Option<usize>| -> String {
+        idx.and_then(|i| row.get(i))
+            .map(|v| cell_to_string(v).trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let entries: Vec<MasterEntry> = rows
+        .iter()
+        .skip(1)
+        .filter_map(|row| {
+            let code = code_rules.normalize(&text(row, Some(code_idx)));
+            if code.is_empty() {
+                return None;
+            }
+            let canonical_code = code_rules.normalize(&text(row, canonical_code_idx));
+
+            Some(MasterEntry {
+                canonical_code: if canonical_code.is_empty() { code.clone() } else { canonical_code },
+                code,
+                canonical_name: text(row, canonical_name_idx),
+                customer_nature: text(row, nature_idx),
+                sales_rep: text(row, sales_rep_idx),
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err("客户主数据中没有有效的客户编码".to_string());
+    }
+
+    Ok(CustomerMaster::from(CustomerMasterFile {
+        file_path: file_path.to_string(),
+        imported_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        entries,
+    }))
+}
@@ -0,0 +1,279 @@
+use crate::admin_division::DivisionNormalizer;
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 导入时的客户编码规范化规则
+///
+/// 默认只去除首尾空白（与原有行为一致），其余规则需要手动开启。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CodeNormalizationRules {
+    // 去除首尾空白
+    pub trim_whitespace: bool,
+    // 去除所有空白（包括中间的空格）
+    pub remove_all_whitespace: bool,
+    // 全角字符转半角（例如 "００１２" → "0012"）
+    pub full_width_to_half_width: bool,
+    // 去除前导零（例如 "001234" → "1234"）
+    pub strip_leading_zeros: bool,
+    // 字母转大写
+    pub uppercase: bool,
+}
+
+impl Default for CodeNormalizationRules {
+    fn default() -> Self {
+        CodeNormalizationRules {
+            trim_whitespace: true,
+            remove_all_whitespace: false,
+            full_width_to_half_width: false,
+            strip_leading_zeros: false,
+            uppercase: false,
+        }
+    }
+}
+
+impl CodeNormalizationRules {
+    /// 质量报告检测编码冲突时使用的规则（空白、全角、前导零）
+    fn collision_rules() -> Self {
+        CodeNormalizationRules {
+            trim_whitespace: true,
+            remove_all_whitespace: true,
+            full_width_to_half_width: true,
+            strip_leading_zeros: true,
+            uppercase: false,
+        }
+    }
+
+    /// 按规则规范化客户编码
+    pub fn normalize(&self, code: &str) -> String {
+        let mut code: String = if self.full_width_to_half_width {
+            code.chars().map(to_half_width).collect()
+        } else {
+            code.to_string()
+        };
+
+        if self.remove_all_whitespace {
+            code.retain(|c| !c.is_whitespace());
+        } else if self.trim_whitespace {
+            code = code.trim().to_string();
+        }
+
+        if self.uppercase {
+            code = code.to_uppercase();
+        }
+
+        if self.strip_leading_zeros {
+            let stripped = code.trim_start_matches('0');
+            code = if stripped.is_empty() && !code.is_empty() {
+                "0".to_string()
+            } else {
+                stripped.to_string()
+            };
+        }
+
+        code
+    }
+}
+
+/// 全角字符转半角（全角空格 U+3000，全角ASCII U+FF01 ~ U+FF5E）
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// 同一编码下的某个客户名称
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NameVariant {
+    pub name: String,
+    pub row_count: usize,
+    pub total_amount: Money,
+}
+
+/// 同一编码对应多个名称
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NameConflict {
+    pub customer_code: String,
+    // 按行数从多到少排序，第一个为出现最多的名称
+    pub names: Vec<NameVariant>,
+}
+
+/// 规范化后相同的某个原始编码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CodeVariant {
+    pub customer_code: String,
+    pub customer_name: String,
+    pub row_count: usize,
+    pub total_amount: Money,
+}
+
+/// 规范化后发生冲突的编码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CodeCollision {
+    pub normalized_code: String,
+    pub codes: Vec<CodeVariant>,
+}
+
+/// 行政区词典中无法匹配的省份或城市
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnmatchedDivision {
+    // province / city
+    pub level: String,
+    pub value: String,
+    pub row_count: usize,
+    pub total_amount: Money,
+}
+
+/// 数据质量报告
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataQualityReport {
+    // 导入时使用的规范化规则
+    pub rules: CodeNormalizationRules,
+    pub total_customers: usize,
+    pub total_rows: usize,
+    pub name_conflicts: Vec<NameConflict>,
+    pub code_collisions: Vec<CodeCollision>,
+    // 按总金额从高到低排序
+    pub unmatched_divisions: Vec<UnmatchedDivision>,
+    // 区县没有内置词典，不会出现在 unmatched_divisions 中；这里是未经检查的区县取值数
+    pub unchecked_districts: usize,
+    pub process_time_ms: u128,
+}
+
+#[derive(Default)]
+struct CustomerStats {
+    row_count: usize,
+    total_amount: Money,
+    // 名称ID → (行数, 金额)
+    names: HashMap<u32, (usize, Money)>,
+}
+
+/// 生成数据质量报告：一个编码多个名称、规范化后编码冲突、无法匹配的省份和城市
+pub fn build_report(
+    store: &ColumnarStore,
+    rules: &CodeNormalizationRules,
+    divisions: &DivisionNormalizer,
+) -> DataQualityReport {
+    let start_time = std::time::Instant::now();
+
+    let mut stats: Vec<CustomerStats> = (0..store.customer_codes.len())
+        .map(|_| CustomerStats::default())
+        .collect();
+    for i in 0..store.len() {
+        let customer = &mut stats[store.customer[i] as usize];
+        let amount = store.total_amount(i);
+        customer.row_count += 1;
+        customer.total_amount += amount;
+        if store.customer_name[i] != NONE_ID {
+            let entry = customer.names.entry(store.customer_name[i]).or_default();
+            entry.0 += 1;
+            entry.1 += amount;
+        }
+    }
+
+    let primary_name = |customer: &CustomerStats| -> String {
+        customer
+            .names
+            .iter()
+            .max_by_key(|(name_id, (count, _))| (*count, std::cmp::Reverse(**name_id)))
+            .and_then(|(name_id, _)| store.customer_names.get(*name_id))
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    // 一个编码多个名称
+    let mut name_conflicts: Vec<NameConflict> = stats
+        .iter()
+        .enumerate()
+        .filter(|(_, customer)| customer.names.len() > 1)
+        .map(|(customer_id, customer)| {
+            let mut names: Vec<NameVariant> = customer
+                .names
+                .iter()
+                .map(|(name_id, (row_count, total_amount))| NameVariant {
+                    name: store.customer_names.get(*name_id).unwrap_or_default().to_string(),
+                    row_count: *row_count,
+                    total_amount: *total_amount,
+                })
+                .collect();
+            names.sort_by(|a, b| b.row_count.cmp(&a.row_count).then_with(|| a.name.cmp(&b.name)));
+
+            NameConflict {
+                customer_code: store.customer_codes.get(customer_id as u32).unwrap_or_default().to_string(),
+                names,
+            }
+        })
+        .collect();
+    name_conflicts.sort_by(|a, b| a.customer_code.cmp(&b.customer_code));
+
+    // 规范化后编码冲突
+    let collision_rules = CodeNormalizationRules::collision_rules();
+    let mut normalized: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (customer_id, code) in store.customer_codes.values().iter().enumerate() {
+        normalized.entry(collision_rules.normalize(code)).or_default().push(customer_id);
+    }
+
+    let code_collisions: Vec<CodeCollision> = normalized
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(normalized_code, ids)| {
+            let mut codes: Vec<CodeVariant> = ids
+                .into_iter()
+                .map(|customer_id| CodeVariant {
+                    customer_code: store.customer_codes.values()[customer_id].clone(),
+                    customer_name: primary_name(&stats[customer_id]),
+                    row_count: stats[customer_id].row_count,
+                    total_amount: stats[customer_id].total_amount,
+                })
+                .collect();
+            codes.sort_by(|a, b| a.customer_code.cmp(&b.customer_code));
+
+            CodeCollision { normalized_code, codes }
+        })
+        .collect();
+
+    // 行政区词典中无法匹配的省份和城市（区县没有内置词典，不检查）
+    let mut unmatched_divisions: Vec<UnmatchedDivision> = Vec::new();
+    for (level, column, dict) in [
+        ("province", &store.province, &store.provinces),
+        ("city", &store.city, &store.cities),
+    ] {
+        let mut totals: HashMap<u32, (usize, Money)> = HashMap::new();
+        for (i, &id) in column.iter().enumerate() {
+            if id != NONE_ID {
+                let entry = totals.entry(id).or_default();
+                entry.0 += 1;
+                entry.1 += store.total_amount(i);
+            }
+        }
+        unmatched_divisions.extend(
+            totals
+                .into_iter()
+                .filter_map(|(id, (row_count, total_amount))| {
+                    let value = dict.get(id)?;
+                    (!divisions.is_standard(level, value)).then(|| UnmatchedDivision {
+                        level: level.to_string(),
+                        value: value.to_string(),
+                        row_count,
+                        total_amount,
+                    })
+                }),
+        );
+    }
+    unmatched_divisions.sort_by(|a, b| b.total_amount.cmp(&a.total_amount).then_with(|| a.value.cmp(&b.value)));
+
+    DataQualityReport {
+        rules: rules.clone(),
+        total_customers: store.customer_codes.len(),
+        total_rows: store.len(),
+        name_conflicts,
+        code_collisions,
+        unmatched_divisions,
+        unchecked_districts: store.districts.len(),
+        process_time_ms: start_time.elapsed().as_millis(),
+    }
+}
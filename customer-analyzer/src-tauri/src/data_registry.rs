@@ -0,0 +1,244 @@
+use crate::aggregate_cube::AggregateCube;
+use crate::columnar_store::ColumnarStore;
+use crate::monthly_analysis::FileLoadResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 默认内存预算（MB）
+pub const DEFAULT_MEMORY_BUDGET_MB: usize = 1536;
+
+/// 单个数据源（或多个数据源合并后）的缓存数据
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DataCache {
+    pub file_path: String,
+    // 列式存储的行数据（仅用于订单明细等需要原始行的查询）
+    pub store: ColumnarStore,
+    // 预聚合立方体（月度分析和排行使用），旧版缓存文件中没有该字段
+    #[serde(default)]
+    pub cube: AggregateCube,
+}
+
+impl DataCache {
+    /// 由文件加载结果构建缓存（取出行数据和立方体，不做复制）
+    pub fn from_load_result(result: &mut FileLoadResult) -> Self {
+        DataCache {
+            file_path: result.file_path.clone(),
+            store: std::mem::take(&mut result.store),
+            cube: std::mem::take(&mut result.cube),
+        }
+    }
+
+    /// 立方体缺失时（旧版缓存文件）由行数据重建，返回是否发生了重建
+    pub fn ensure_cube(&mut self) -> bool {
+        if !self.cube.is_empty() || self.store.len() == 0 {
+            return false;
+        }
+        self.cube = AggregateCube::build(&self.store);
+        true
+    }
+
+    /// 合并多个数据源的缓存
+    pub fn merge(caches: &[Arc<DataCache>]) -> Self {
+        let total_rows: usize = caches.iter().map(|c| c.store.len()).sum();
+        let mut merged_store = ColumnarStore::with_capacity(total_rows);
+        let mut merged_cube = AggregateCube::default();
+        let mut merged_file_paths: Vec<String> = Vec::with_capacity(caches.len());
+
+        for cache in caches {
+            merged_file_paths.push(cache.file_path.clone());
+            merged_store.append(&cache.store);
+            merged_cube.merge(&cache.cube);
+        }
+
+        DataCache {
+            file_path: merged_file_paths.join("; "),
+            store: merged_store,
+            cube: merged_cube,
+        }
+    }
+
+    /// 估算缓存占用的内存（字节）
+    pub fn estimated_size(&self) -> usize {
+        self.store.estimated_size() + self.cube.estimated_size() + self.file_path.capacity()
+    }
+}
+
+/// 注册表条目信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistryEntryInfo {
+    pub key: String,
+    pub data_source_ids: Vec<String>,
+    pub is_dataset: bool,
+    pub total_rows: usize,
+    pub size_bytes: usize,
+}
+
+/// 注册表状态
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistryStatus {
+    pub budget_bytes: usize,
+    pub used_bytes: usize,
+    pub entries: Vec<RegistryEntryInfo>,
+}
+
+struct RegistryEntry {
+    data_source_ids: Vec<String>,
+    cache: Arc<DataCache>,
+    size_bytes: usize,
+    last_used: u64,
+}
+
+struct RegistryInner {
+    entries: HashMap<String, RegistryEntry>,
+    budget_bytes: usize,
+    clock: u64,
+}
+
+impl RegistryInner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn used_bytes(&self) -> usize {
+        self.entries.values().map(|e| e.size_bytes).sum()
+    }
+
+    /// 超出预算时按最近最少使用顺序淘汰，`keep` 指定的条目不淘汰
+    fn evict(&mut self, keep: Option<&str>) {
+        while self.used_bytes() > self.budget_bytes {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            match victim {
+                Some(key) => {
+                    self.entries.remove(&key);
+                },
+                None => break,
+            }
+        }
+    }
+}
+
+/// 内存中的数据源 / 数据集注册表（LRU 淘汰，受内存预算限制）
+///
+/// 单个数据源以其ID为键，多个数据源合并后的数据集以排序后的ID组合为键。
+pub struct DataRegistry {
+    inner: Mutex<RegistryInner>,
+}
+
+impl DataRegistry {
+    pub fn new(budget_bytes: usize) -> Self {
+        DataRegistry {
+            inner: Mutex::new(RegistryInner {
+                entries: HashMap::new(),
+                budget_bytes,
+                clock: 0,
+            }),
+        }
+    }
+
+    /// 数据源ID列表去重排序，作为注册表的键
+    pub fn normalize_ids(data_source_ids: &[String]) -> Vec<String> {
+        let mut ids = data_source_ids.to_vec();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn key_of(ids: &[String]) -> String {
+        ids.join("+")
+    }
+
+    /// 获取已加载的数据源或数据集
+    pub fn get(&self, data_source_ids: &[String]) -> Option<Arc<DataCache>> {
+        let key = Self::key_of(&Self::normalize_ids(data_source_ids));
+        let mut inner = self.inner.lock().unwrap();
+        let now = inner.tick();
+
+        inner.entries.get_mut(&key).map(|entry| {
+            entry.last_used = now;
+            entry.cache.clone()
+        })
+    }
+
+    /// 放入注册表，必要时淘汰最久未使用的条目
+    pub fn insert(&self, data_source_ids: &[String], cache: DataCache) -> Arc<DataCache> {
+        let ids = Self::normalize_ids(data_source_ids);
+        let key = Self::key_of(&ids);
+        let size_bytes = cache.estimated_size();
+        let cache = Arc::new(cache);
+
+        let mut inner = self.inner.lock().unwrap();
+        let now = inner.tick();
+        inner.entries.insert(
+            key.clone(),
+            RegistryEntry {
+                data_source_ids: ids,
+                cache: cache.clone(),
+                size_bytes,
+                last_used: now,
+            },
+        );
+        inner.evict(Some(&key));
+
+        cache
+    }
+
+    /// 数据源是否已在内存中
+    pub fn contains_source(&self, data_source_id: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .contains_key(data_source_id)
+    }
+
+    /// 移除数据源以及包含它的所有数据集
+    pub fn remove_source(&self, data_source_id: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .retain(|_, entry| !entry.data_source_ids.iter().any(|id| id == data_source_id));
+    }
+
+    /// 清空注册表
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    /// 设置内存预算
+    pub fn set_budget(&self, budget_bytes: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.budget_bytes = budget_bytes;
+        inner.evict(None);
+    }
+
+    /// 注册表状态（按最近使用排序）
+    pub fn status(&self) -> RegistryStatus {
+        let inner = self.inner.lock().unwrap();
+        let mut entries: Vec<(&String, &RegistryEntry)> = inner.entries.iter().collect();
+        entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_used));
+
+        RegistryStatus {
+            budget_bytes: inner.budget_bytes,
+            used_bytes: inner.used_bytes(),
+            entries: entries
+                .into_iter()
+                .map(|(key, entry)| RegistryEntryInfo {
+                    key: key.clone(),
+                    data_source_ids: entry.data_source_ids.clone(),
+                    is_dataset: entry.data_source_ids.len() > 1,
+                    total_rows: entry.cache.store.len(),
+                    size_bytes: entry.size_bytes,
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::aggregate_cube::CubeCell;
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::excel_processor::{percent_of, RankDirection, RankMetric};
+use crate::geography::customer_keys;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 维度排行参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DimensionRankOptions {
+    // 排行维度：province / city / district / region / customer / custom:列名
+    pub analysis_type: String,
+    pub n: usize,
+    pub metric: RankMetric,
+    pub direction: RankDirection,
+    // 上级维度及其取值（例如在某个省份内对城市排行）
+    pub parent_type: Option<String>,
+    pub parent_value: Option<String>,
+}
+
+impl Default for DimensionRankOptions {
+    fn default() -> Self {
+        DimensionRankOptions {
+            analysis_type: "province".to_string(),
+            n: 20,
+            metric: RankMetric::TotalAmount,
+            direction: RankDirection::Desc,
+            parent_type: None,
+            parent_value: None,
+        }
+    }
+}
+
+/// 排行中的一个分组
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankedGroup {
+    pub rank: usize,
+    pub value: String,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
+    pub order_count: u32,
+    pub customer_count: usize,
+    pub metric_value: f64,
+    pub share: f64,
+    pub cumulative_share: f64,
+}
+
+/// 维度排行结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DimensionRankResult {
+    pub options: DimensionRankOptions,
+    // 可继续下钻的下级维度（省份 → 城市 → 区县）
+    pub next_level: Option<String>,
+    pub groups: Vec<RankedGroup>,
+    pub total_groups: usize,
+    pub metric_total: f64,
+    pub total_amount: Money,
+    pub process_time_ms: u128,
+}
+
+/// 地理维度的下一级
+pub fn next_level(analysis_type: &str) -> Option<&'static str> {
+    match analysis_type {
+        "province" => Some("city"),
+        "city" => Some("district"),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct GroupAccumulator {
+    // 分组中首次出现的取值ID（客户维度合并别名后用于取标准编码）
+    id: u32,
+    cell: CubeCell,
+    customers: HashSet<u32>,
+}
+
+/// 按任意维度排行
+///
+/// 不重复客户数不能跨月份或数据源相加，因此直接基于行数据（整数ID）统计。
+/// 有客户主数据时按标准客户去重，客户维度的别名编码合并为一行。
+pub fn rank(
+    store: &ColumnarStore,
+    options: &DimensionRankOptions,
+    master: Option<&CustomerMaster>,
+) -> Result<DimensionRankResult, String> {
+    let start_time = std::time::Instant::now();
+
+    let (column, dict) = store
+        .dimension(&options.analysis_type)
+        .ok_or_else(|| format!("不支持的维度: {}", options.analysis_type))?;
+
+    // 上级维度筛选
+    let parent = match (&options.parent_type, &options.parent_value) {
+        (Some(parent_type), Some(parent_value)) => {
+            let (parent_column, parent_dict) = store
+                .dimension(parent_type)
+                .ok_or_else(|| format!("不支持的维度: {}", parent_type))?;
+            let parent_id = parent_dict
+                .lookup(parent_value)
+                .ok_or_else(|| format!("未找到: {}", parent_value))?;
+            Some((parent_column, parent_id))
+        },
+        _ => None,
+    };
+
+    let customers = customer_keys(store, master);
+    let merge_aliases = master.is_some() && options.analysis_type == "customer";
+
+    let mut groups: HashMap<u32, GroupAccumulator> = HashMap::new();
+    for (i, &id) in column.iter().enumerate() {
+        if id == NONE_ID {
+            continue;
+        }
+        if let Some((parent_column, parent_id)) = parent {
+            if parent_column[i] != parent_id {
+                continue;
+            }
+        }
+
+        let key = if merge_aliases { customers[id as usize] } else { id };
+        let group = groups.entry(key).or_insert_with(|| GroupAccumulator {
+            id,
+            ..Default::default()
+        });
+        group.cell.add_row(store.pay_amount[i], store.recharge_deduction[i], store.total_amount(i));
+        group.customers.insert(customers[store.customer[i] as usize]);
+    }
+
+    let metric = options.metric;
+    let total_groups = groups.len();
+    let mut total = CubeCell::default();
+    for group in groups.values() {
+        total.add(&group.cell);
+    }
+    let metric_total = total.metric_value(metric);
+
+    let mut groups: Vec<(&str, GroupAccumulator)> = groups
+        .into_values()
+        .map(|group| {
+            let value = dict.get(group.id).unwrap_or_default();
+            match master {
+                Some(master) if merge_aliases => (master.canonical_code(value), group),
+                _ => (value, group),
+            }
+        })
+        .collect();
+    groups.sort_by(|(a_value, a), (b_value, b)| {
+        let ordering = a
+            .cell
+            .metric_value(metric)
+            .partial_cmp(&b.cell.metric_value(metric))
+            .unwrap_or(std::cmp::Ordering::Equal);
+        let ordering = match options.direction {
+            RankDirection::Desc => ordering.reverse(),
+            RankDirection::Asc => ordering,
+        };
+        ordering.then_with(|| a_value.cmp(b_value))
+    });
+
+    let mut cumulative = 0.0;
+    let groups: Vec<RankedGroup> = groups
+        .into_iter()
+        .take(options.n)
+        .enumerate()
+        .map(|(index, (value, group))| {
+            let metric_value = group.cell.metric_value(metric);
+            cumulative += metric_value;
+            RankedGroup {
+                rank: index + 1,
+                value: value.to_string(),
+                pay_amount: group.cell.pay_amount,
+                recharge_deduction: group.cell.recharge_deduction,
+                total_amount: group.cell.total_amount,
+                order_count: group.cell.order_count,
+                customer_count: group.customers.len(),
+                metric_value,
+                share: percent_of(metric_value, metric_total),
+                cumulative_share: percent_of(cumulative, metric_total),
+            }
+        })
+        .collect();
+
+    Ok(DimensionRankResult {
+        options: options.clone(),
+        next_level: next_level(&options.analysis_type).map(str::to_string),
+        groups,
+        total_groups,
+        metric_total,
+        total_amount: total.total_amount,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
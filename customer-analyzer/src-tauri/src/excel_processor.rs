@@ -5,36 +5,143 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::path::Path;
 
+use crate::data_quality::CodeNormalizationRules;
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomerData {
     pub customer_code: String,
     pub customer_name: String,
-    pub pay_amount: f64,
-    pub recharge_deduction: f64,
-    pub total_amount: f64,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
     pub order_count: u32,
+    // 客户性质、业务员（来自客户主数据）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer_nature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sales_rep: Option<String>,
+}
+
+/// 排名指标
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RankMetric {
+    #[default]
+    TotalAmount,
+    PayAmount,
+    RechargeDeduction,
+    OrderCount,
+}
+
+impl RankMetric {
+    /// 指标值（金额以元为单位，仅用于排序和计算比例）
+    pub fn value_of(&self, customer: &CustomerData) -> f64 {
+        match self {
+            RankMetric::TotalAmount => customer.total_amount.to_yuan(),
+            RankMetric::PayAmount => customer.pay_amount.to_yuan(),
+            RankMetric::RechargeDeduction => customer.recharge_deduction.to_yuan(),
+            RankMetric::OrderCount => customer.order_count as f64,
+        }
+    }
+
+    /// 指标的精确合计（金额按定点数相加）
+    pub fn sum_of<'a>(&self, customers: impl Iterator<Item = &'a CustomerData>) -> f64 {
+        match self {
+            RankMetric::TotalAmount => customers.map(|c| c.total_amount).sum::<Money>().to_yuan(),
+            RankMetric::PayAmount => customers.map(|c| c.pay_amount).sum::<Money>().to_yuan(),
+            RankMetric::RechargeDeduction => customers.map(|c| c.recharge_deduction).sum::<Money>().to_yuan(),
+            RankMetric::OrderCount => customers.map(|c| c.order_count as u64).sum::<u64>() as f64,
+        }
+    }
+}
+
+/// 排序方向：desc 为前N名，asc 为后N名
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RankDirection {
+    #[default]
+    Desc,
+    Asc,
+}
+
+/// 前N名排行参数（前端未传的字段使用默认值）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TopNOptions {
+    pub n: usize,
+    pub metric: RankMetric,
+    pub direction: RankDirection,
+    // 总金额低于该值的客户不参与排名
+    pub min_amount: Option<f64>,
+}
+
+impl Default for TopNOptions {
+    fn default() -> Self {
+        TopNOptions {
+            n: 20,
+            metric: RankMetric::TotalAmount,
+            direction: RankDirection::Desc,
+            min_amount: None,
+        }
+    }
+}
+
+/// 排行中的客户
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankedCustomer {
+    pub rank: usize,
+    #[serde(flatten)]
+    pub customer: CustomerData,
+    // 排名指标的值
+    pub metric_value: f64,
+    // 占全部客户指标合计的比例（%）
+    pub share: f64,
+    // 截至当前名次的累计占比（%）
+    pub cumulative_share: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalysisResult {
-    pub top20: Vec<CustomerData>,
+    pub top_customers: Vec<RankedCustomer>,
+    pub options: TopNOptions,
     pub total_customers: usize,
-    pub total_amount: f64,
-    pub top20_amount: f64,
+    // 满足最低金额条件、参与排名的客户数
+    pub eligible_customers: usize,
+    pub total_amount: Money,
+    pub top_amount: Money,
+    // 全部客户排名指标的合计
+    pub metric_total: f64,
     pub total_rows: usize,
     pub process_time_ms: u128,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ProcessProgress {
     pub step: String,
     pub message: String,
     pub percent: u32,
     pub detail: String,
+    // 流式读取阶段的统计信息
+    #[serde(default)]
+    pub rows_read: Option<u64>,
+    #[serde(default)]
+    pub estimated_total_rows: Option<u64>,
+    #[serde(default)]
+    pub rows_per_second: Option<f64>,
+    #[serde(default)]
+    pub eta_seconds: Option<f64>,
+    // 已从文件读取的字节数及文件大小
+    #[serde(default)]
+    pub bytes_read: Option<u64>,
+    #[serde(default)]
+    pub total_bytes: Option<u64>,
 }
 
 pub fn process_excel_file<F>(
     file_path: &str,
+    options: &TopNOptions,
+    code_rules: &CodeNormalizationRules,
     cancel_flag: Arc<Mutex<bool>>,
     progress_callback: F,
 ) -> Result<AnalysisResult, String>
@@ -56,6 +163,7 @@ where
         message: "正在打开Excel文件...".to_string(),
         percent: 5,
         detail: format!("文件: {}", path.file_name().unwrap_or_default().to_string_lossy()),
+        ..Default::default()
     });
 
     // 检查是否取消
@@ -74,6 +182,7 @@ where
                 message: "正在解析Excel数据...".to_string(),
                 percent: 15,
                 detail: "读取工作表中...".to_string(),
+                ..Default::default()
             });
 
             let sheet_name = workbook.sheet_names().first()
@@ -93,6 +202,7 @@ where
                 message: "正在解析Excel数据...".to_string(),
                 percent: 15,
                 detail: "读取工作表中...".to_string(),
+                ..Default::default()
             });
 
             let sheet_name = workbook.sheet_names().first()
@@ -122,6 +232,7 @@ where
         message: "数据读取完成".to_string(),
         percent: 30,
         detail: format!("共 {} 行数据", total_rows),
+        ..Default::default()
     });
 
     // 解析表头，找到列索引
@@ -133,6 +244,7 @@ where
         message: "正在分析客户数据...".to_string(),
         percent: 40,
         detail: "使用多线程并行处理...".to_string(),
+        ..Default::default()
     });
 
     // 检查是否取消
@@ -151,7 +263,7 @@ where
             let mut local_map: HashMap<String, CustomerData> = HashMap::new();
             
             for row in chunk {
-                if let Some(customer) = parse_row(row, &col_indices) {
+                if let Some(customer) = parse_row(row, &col_indices, code_rules) {
                     local_map
                         .entry(customer.customer_code.clone())
                         .and_modify(|existing| {
@@ -181,6 +293,7 @@ where
         message: "正在合并处理结果...".to_string(),
         percent: 70,
         detail: format!("处理了 {} 个数据块", partial_maps.len()),
+        ..Default::default()
     });
 
     // 合并所有部分结果
@@ -207,38 +320,94 @@ where
         message: "正在生成排行榜...".to_string(),
         percent: 85,
         detail: format!("发现 {} 个不同客户", customer_map.len()),
+        ..Default::default()
     });
 
-    // 转换为Vec并排序
-    let mut customers: Vec<CustomerData> = customer_map.into_values().collect();
-    customers.par_sort_by(|a, b| {
-        b.total_amount
-            .partial_cmp(&a.total_amount)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    // 计算统计数据
-    let total_amount: f64 = customers.iter().map(|c| c.total_amount).sum();
-    let top20: Vec<CustomerData> = customers.iter().take(20).cloned().collect();
-    let top20_amount: f64 = top20.iter().map(|c| c.total_amount).sum();
-
-    let process_time_ms = start_time.elapsed().as_millis();
+    let customers: Vec<CustomerData> = customer_map.into_values().collect();
+    let mut result = rank_customers(customers, options, total_rows);
+    result.process_time_ms = start_time.elapsed().as_millis();
 
     progress_callback(ProcessProgress {
         step: "完成".to_string(),
         message: "分析完成！".to_string(),
         percent: 100,
-        detail: format!("耗时 {}ms", process_time_ms),
+        detail: format!("耗时 {}ms", result.process_time_ms),
+        ..Default::default()
+    });
+
+    Ok(result)
+}
+
+/// 按指定指标对客户排名，返回前（或后）N名及其名次和累计占比
+pub fn rank_customers(
+    mut customers: Vec<CustomerData>,
+    options: &TopNOptions,
+    total_rows: usize,
+) -> AnalysisResult {
+    let metric = options.metric;
+    let total_customers = customers.len();
+    let total_amount: Money = customers.iter().map(|c| c.total_amount).sum();
+    let metric_total = metric.sum_of(customers.iter());
+
+    if let Some(min_amount) = options.min_amount {
+        let min_amount = Money::from_yuan(min_amount);
+        customers.retain(|c| c.total_amount >= min_amount);
+    }
+    let eligible_customers = customers.len();
+
+    // 指标相同时按客户编码排序，保证结果稳定
+    customers.par_sort_by(|a, b| {
+        let ordering = metric
+            .value_of(a)
+            .partial_cmp(&metric.value_of(b))
+            .unwrap_or(std::cmp::Ordering::Equal);
+        let ordering = match options.direction {
+            RankDirection::Desc => ordering.reverse(),
+            RankDirection::Asc => ordering,
+        };
+        ordering.then_with(|| a.customer_code.cmp(&b.customer_code))
     });
 
-    Ok(AnalysisResult {
-        top20,
-        total_customers: customers.len(),
+    let mut cumulative = 0.0;
+    let top_customers: Vec<RankedCustomer> = customers
+        .into_iter()
+        .take(options.n)
+        .enumerate()
+        .map(|(index, customer)| {
+            let metric_value = metric.value_of(&customer);
+            cumulative += metric_value;
+            RankedCustomer {
+                rank: index + 1,
+                customer,
+                metric_value,
+                share: percent_of(metric_value, metric_total),
+                cumulative_share: percent_of(cumulative, metric_total),
+            }
+        })
+        .collect();
+
+    let top_amount: Money = top_customers.iter().map(|c| c.customer.total_amount).sum();
+
+    AnalysisResult {
+        top_customers,
+        options: options.clone(),
+        total_customers,
+        eligible_customers,
         total_amount,
-        top20_amount,
+        top_amount,
+        metric_total,
         total_rows,
-        process_time_ms,
-    })
+        process_time_ms: 0,
+    }
+}
+
+/// 占比（%），合计为0时返回0
+pub fn percent_of(value: f64, total: f64) -> f64 {
+    if total != 0.0 {
+        value / total * 100.0
+    } else {
+        0.0
+    }
 }
 
 #[derive(Debug)]
@@ -278,10 +447,10 @@ fn find_column_indices(header: &[Data]) -> Result<ColumnIndices, String> {
     })
 }
 
-fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CustomerData> {
+fn parse_row(row: &[Data], indices: &ColumnIndices, code_rules: &CodeNormalizationRules) -> Option<CustomerData> {
     let customer_code = row
         .get(indices.customer_code)
-        .map(|v| data_to_string(v).trim().to_string())?;
+        .map(|v| code_rules.normalize(&data_to_string(v)))?;
     
     if customer_code.is_empty() {
         return None;
@@ -295,13 +464,13 @@ fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CustomerData> {
 
     let pay_amount = row
         .get(indices.pay_amount)
-        .map(|v| parse_number(v))
-        .unwrap_or(0.0);
+        .map(Money::from_data)
+        .unwrap_or_default();
 
     let recharge_deduction = row
         .get(indices.recharge_deduction)
-        .map(|v| parse_number(v))
-        .unwrap_or(0.0);
+        .map(Money::from_data)
+        .unwrap_or_default();
 
     let total_amount = pay_amount + recharge_deduction;
 
@@ -312,6 +481,8 @@ fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CustomerData> {
         recharge_deduction,
         total_amount,
         order_count: 1,
+        customer_nature: None,
+        sales_rep: None,
     })
 }
 
@@ -329,11 +500,3 @@ fn data_to_string(value: &Data) -> String {
     }
 }
 
-fn parse_number(value: &Data) -> f64 {
-    match value {
-        Data::Float(f) => *f,
-        Data::Int(i) => *i as f64,
-        Data::String(s) => s.trim().parse().unwrap_or(0.0),
-        _ => 0.0,
-    }
-}
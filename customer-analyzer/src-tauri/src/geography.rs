@@ -0,0 +1,240 @@
+use crate::aggregate_cube::CubeCell;
+use crate::columnar_store::{ColumnarStore, Dictionary, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::monthly_analysis::{self, MonthlySalesData};
+use crate::money::Money;
+use crate::period::TrendOptions;
+use crate::pivot::EMPTY_LABEL;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 地理层级（省份 → 城市 → 区县）
+const LEVELS: [&str; 3] = ["province", "city", "district"];
+
+/// 地理树中的一个节点
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeoNode {
+    pub level: String,
+    pub name: String,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
+    pub order_count: u32,
+    pub customer_count: usize,
+    // 按总金额从高到低排列
+    pub children: Vec<GeoNode>,
+}
+
+/// 地理树
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeoTreeResult {
+    pub provinces: Vec<GeoNode>,
+    pub total_amount: Money,
+    pub customer_count: usize,
+    pub process_time_ms: u128,
+}
+
+/// 下钻结果中的一个下级节点及其趋势
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeoDrillChild {
+    pub level: String,
+    pub name: String,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
+    pub order_count: u32,
+    pub customer_count: usize,
+    // 是否还能继续下钻
+    pub has_children: bool,
+    pub trend: Vec<MonthlySalesData>,
+    pub unknown_period: Option<MonthlySalesData>,
+}
+
+/// 下钻结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeoDrillDownResult {
+    // 当前节点的路径（空路径为全国）
+    pub path: Vec<String>,
+    // 下级层级：province / city / district
+    pub level: String,
+    pub options: TrendOptions,
+    pub children: Vec<GeoDrillChild>,
+    pub process_time_ms: u128,
+}
+
+#[derive(Default)]
+struct NodeAccumulator {
+    cell: CubeCell,
+    customers: HashSet<u32>,
+    children: HashMap<u32, NodeAccumulator>,
+}
+
+fn dictionaries(store: &ColumnarStore) -> [(&[u32], &Dictionary); 3] {
+    [
+        (&store.province, &store.provinces),
+        (&store.city, &store.cities),
+        (&store.district, &store.districts),
+    ]
+}
+
+/// 第 i 行的层级ID；末尾为空的层级不计入（例如只有省份的行只到省份一级）
+fn row_path(levels: &[(&[u32], &Dictionary); 3], i: usize) -> Vec<u32> {
+    let mut path: Vec<u32> = levels.iter().map(|(column, _)| column[i]).collect();
+    while path.len() > 1 && path.last() == Some(&NONE_ID) {
+        path.pop();
+    }
+    path
+}
+
+/// 客户编码ID → 用于去重的客户序号（有客户主数据时同一标准客户的编码序号相同）
+pub fn customer_keys(store: &ColumnarStore, master: Option<&CustomerMaster>) -> Vec<u32> {
+    match master {
+        Some(master) => master.canonical_ids(store.customer_codes.values()),
+        None => (0..store.customer_codes.len() as u32).collect(),
+    }
+}
+
+fn label(dict: &Dictionary, id: u32) -> String {
+    dict.get(id).unwrap_or(EMPTY_LABEL).to_string()
+}
+
+fn sort_by_amount<T>(nodes: &mut [T], amount: impl Fn(&T) -> Money) {
+    nodes.sort_by_key(|node| std::cmp::Reverse(amount(node)));
+}
+
+fn to_node(levels: &[(&[u32], &Dictionary); 3], depth: usize, id: u32, acc: NodeAccumulator) -> GeoNode {
+    let mut children: Vec<GeoNode> = acc
+        .children
+        .into_iter()
+        .map(|(child_id, child)| to_node(levels, depth + 1, child_id, child))
+        .collect();
+    sort_by_amount(&mut children, |node| node.total_amount);
+
+    GeoNode {
+        level: LEVELS[depth].to_string(),
+        name: label(levels[depth].1, id),
+        pay_amount: acc.cell.pay_amount,
+        recharge_deduction: acc.cell.recharge_deduction,
+        total_amount: acc.cell.total_amount,
+        order_count: acc.cell.order_count,
+        customer_count: acc.customers.len(),
+        children,
+    }
+}
+
+/// 构建省份 → 城市 → 区县的地理树，每个节点包含金额、订单数和不重复客户数
+pub fn build_tree(store: &ColumnarStore, master: Option<&CustomerMaster>) -> GeoTreeResult {
+    let start_time = std::time::Instant::now();
+    let levels = dictionaries(store);
+    let customer_ids = customer_keys(store, master);
+
+    let mut roots: HashMap<u32, NodeAccumulator> = HashMap::new();
+    let mut customers: HashSet<u32> = HashSet::new();
+    let mut total_amount = Money::ZERO;
+
+    for i in 0..store.len() {
+        let amount = store.total_amount(i);
+        let customer = customer_ids[store.customer[i] as usize];
+        total_amount += amount;
+        customers.insert(customer);
+
+        let path = row_path(&levels, i);
+        let mut nodes = &mut roots;
+        for id in path {
+            let node = nodes.entry(id).or_default();
+            node.cell.add_row(store.pay_amount[i], store.recharge_deduction[i], amount);
+            node.customers.insert(customer);
+            nodes = &mut node.children;
+        }
+    }
+
+    let mut provinces: Vec<GeoNode> = roots
+        .into_iter()
+        .map(|(id, acc)| to_node(&levels, 0, id, acc))
+        .collect();
+    sort_by_amount(&mut provinces, |node| node.total_amount);
+
+    GeoTreeResult {
+        provinces,
+        total_amount,
+        customer_count: customers.len(),
+        process_time_ms: start_time.elapsed().as_millis(),
+    }
+}
+
+/// 下钻：返回某个节点（按路径指定，空路径为全国）的下级节点及各自的趋势
+pub fn drill_down(
+    store: &ColumnarStore,
+    path: &[String],
+    options: &TrendOptions,
+    master: Option<&CustomerMaster>,
+) -> Result<GeoDrillDownResult, String> {
+    let start_time = std::time::Instant::now();
+    options.validate()?;
+    let customer_ids = customer_keys(store, master);
+
+    if path.len() >= LEVELS.len() {
+        return Err("区县已是最下级，无法继续下钻".to_string());
+    }
+    let levels = dictionaries(store);
+    let depth = path.len();
+
+    // 路径中的名称 → 字典ID（"（空）"对应空值）
+    let mut path_ids: Vec<u32> = Vec::with_capacity(depth);
+    for (level, name) in path.iter().enumerate() {
+        let id = if name == EMPTY_LABEL {
+            NONE_ID
+        } else {
+            levels[level].1.lookup(name).ok_or_else(|| format!("未找到: {}", name))?
+        };
+        path_ids.push(id);
+    }
+
+    // 下级ID → 行号
+    let mut children: HashMap<u32, Vec<usize>> = HashMap::new();
+    for i in 0..store.len() {
+        let row = row_path(&levels, i);
+        if row.len() <= depth || row[..depth] != path_ids[..] {
+            continue;
+        }
+        children.entry(row[depth]).or_default().push(i);
+    }
+
+    let mut children: Vec<GeoDrillChild> = children
+        .into_par_iter()
+        .map(|(id, rows)| {
+            let mut cell = CubeCell::default();
+            let mut customers: HashSet<u32> = HashSet::new();
+            let mut has_children = false;
+            for &i in &rows {
+                cell.add_row(store.pay_amount[i], store.recharge_deduction[i], store.total_amount(i));
+                customers.insert(customer_ids[store.customer[i] as usize]);
+                has_children |= row_path(&levels, i).len() > depth + 1;
+            }
+            let series = monthly_analysis::trend_for_rows(store, rows, options);
+
+            GeoDrillChild {
+                level: LEVELS[depth].to_string(),
+                name: label(levels[depth].1, id),
+                pay_amount: cell.pay_amount,
+                recharge_deduction: cell.recharge_deduction,
+                total_amount: cell.total_amount,
+                order_count: cell.order_count,
+                customer_count: customers.len(),
+                has_children,
+                trend: series.data,
+                unknown_period: series.unknown,
+            }
+        })
+        .collect();
+    sort_by_amount(&mut children, |child| child.total_amount);
+
+    Ok(GeoDrillDownResult {
+        path: path.to_vec(),
+        level: LEVELS[depth].to_string(),
+        options: options.clone(),
+        children,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
@@ -0,0 +1,236 @@
+use crate::excel_processor::ProcessProgress;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// 最多保留的已结束任务数量
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// 任务状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 任务信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub progress: Option<ProcessProgress>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+/// 带任务ID的进度事件
+#[derive(Debug, Serialize, Clone)]
+pub struct JobProgressEvent {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub progress: ProcessProgress,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancel_flag: Arc<Mutex<bool>>,
+    done: watch::Sender<bool>,
+    // 创建顺序，用于任务列表排序
+    seq: u64,
+    // 结束顺序，用于清理最早结束的任务
+    finished_seq: u64,
+}
+
+/// 任务管理器：每个耗时操作对应一个独立的任务
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    next_seq: Mutex<u64>,
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        JobManager {
+            jobs: Mutex::new(HashMap::new()),
+            next_seq: Mutex::new(0),
+        }
+    }
+
+    /// 创建任务，返回任务ID和该任务专属的取消标志
+    pub fn create(&self, kind: &str) -> (String, Arc<Mutex<bool>>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let cancel_flag = Arc::new(Mutex::new(false));
+        let (done, _) = watch::channel(false);
+
+        let seq = self.next_seq();
+
+        let entry = JobEntry {
+            info: JobInfo {
+                id: id.clone(),
+                kind: kind.to_string(),
+                status: JobStatus::Running,
+                created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                finished_at: None,
+                progress: None,
+                result: None,
+                error: None,
+            },
+            cancel_flag: cancel_flag.clone(),
+            done,
+            seq,
+            finished_seq: 0,
+        };
+
+        self.jobs.lock().unwrap().insert(id.clone(), entry);
+        (id, cancel_flag)
+    }
+
+    /// 记录任务的最新进度
+    pub fn update_progress(&self, job_id: &str, progress: &ProcessProgress) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(job_id) {
+            entry.info.progress = Some(progress.clone());
+        }
+    }
+
+    /// 结束任务并保存结果或错误
+    pub fn finish<T: Serialize>(&self, job_id: &str, result: &Result<T, String>) {
+        let finished_seq = self.next_seq();
+        let mut jobs = self.jobs.lock().unwrap();
+
+        if let Some(entry) = jobs.get_mut(job_id) {
+            let cancelled = *entry.cancel_flag.lock().unwrap();
+
+            match result {
+                Ok(value) => match serde_json::to_value(value) {
+                    Ok(value) => {
+                        entry.info.status = JobStatus::Completed;
+                        entry.info.result = Some(value);
+                    },
+                    Err(e) => {
+                        entry.info.status = JobStatus::Failed;
+                        entry.info.error = Some(format!("序列化任务结果失败: {}", e));
+                    },
+                },
+                Err(e) => {
+                    entry.info.status = if cancelled { JobStatus::Cancelled } else { JobStatus::Failed };
+                    entry.info.error = Some(e.clone());
+                },
+            }
+
+            entry.info.finished_at = Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+            entry.finished_seq = finished_seq;
+            // 没有订阅者时 send 不会保存值，这里必须用 send_replace
+            entry.done.send_replace(true);
+        }
+
+        Self::prune_finished(&mut jobs);
+    }
+
+    /// 取消单个任务
+    pub fn cancel(&self, job_id: &str) -> Result<(), String> {
+        let jobs = self.jobs.lock().unwrap();
+        let entry = jobs.get(job_id).ok_or("任务不存在")?;
+
+        if entry.info.status != JobStatus::Running {
+            return Err("任务已结束".to_string());
+        }
+
+        *entry.cancel_flag.lock().unwrap() = true;
+        Ok(())
+    }
+
+    /// 取消所有正在运行的任务
+    pub fn cancel_all(&self) {
+        let jobs = self.jobs.lock().unwrap();
+        for entry in jobs.values() {
+            if entry.info.status == JobStatus::Running {
+                *entry.cancel_flag.lock().unwrap() = true;
+            }
+        }
+    }
+
+    /// 任务列表（不含结果数据），按创建时间倒序
+    pub fn list(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut entries: Vec<&JobEntry> = jobs.values().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.seq));
+
+        entries
+            .into_iter()
+            .map(|entry| JobInfo {
+                result: None,
+                ..entry.info.clone()
+            })
+            .collect()
+    }
+
+    /// 获取任务详情（含结果数据）
+    pub fn get(&self, job_id: &str) -> Option<JobInfo> {
+        self.jobs.lock().unwrap().get(job_id).map(|entry| entry.info.clone())
+    }
+
+    /// 删除已结束的任务
+    pub fn remove(&self, job_id: &str) -> Result<(), String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        match jobs.get(job_id) {
+            Some(entry) if entry.info.status == JobStatus::Running => {
+                Err("任务仍在运行，请先取消".to_string())
+            },
+            Some(_) => {
+                jobs.remove(job_id);
+                Ok(())
+            },
+            None => Err("任务不存在".to_string()),
+        }
+    }
+
+    /// 等待任务结束并返回任务详情
+    pub async fn wait(&self, job_id: &str) -> Result<JobInfo, String> {
+        let mut done = {
+            let jobs = self.jobs.lock().unwrap();
+            jobs.get(job_id).ok_or("任务不存在")?.done.subscribe()
+        };
+
+        done.wait_for(|finished| *finished)
+            .await
+            .map_err(|_| "任务已被删除".to_string())?;
+
+        self.get(job_id).ok_or_else(|| "任务已被删除".to_string())
+    }
+
+    fn next_seq(&self) -> u64 {
+        let mut next_seq = self.next_seq.lock().unwrap();
+        *next_seq += 1;
+        *next_seq
+    }
+
+    fn prune_finished(jobs: &mut HashMap<String, JobEntry>) {
+        let mut finished: Vec<(u64, String)> = jobs
+            .values()
+            .filter(|entry| entry.info.status != JobStatus::Running)
+            .map(|entry| (entry.finished_seq, entry.info.id.clone()))
+            .collect();
+
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED_JOBS;
+        for (_, id) in finished.into_iter().take(excess) {
+            jobs.remove(&id);
+        }
+    }
+}
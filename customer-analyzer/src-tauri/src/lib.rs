@@ -1,10 +1,47 @@
+mod abc_analysis;
+mod admin_division;
+mod aggregate_cube;
+mod cohort;
+mod columnar_store;
+mod concentration;
+mod customer_lifecycle;
+mod customer_master;
+mod data_quality;
+mod data_registry;
+mod dimension_ranking;
 mod excel_processor;
+mod geography;
+mod job_manager;
+mod money;
 mod monthly_analysis;
 mod out_of_policy;
-
-use excel_processor::{AnalysisResult, ProcessProgress, CustomerData};
-use monthly_analysis::{MonthlyAnalysisResult, CachedRow, CustomerOption};
+mod period;
+mod pivot;
+mod rank_movers;
+mod rfm;
+mod row_filter;
+
+use abc_analysis::{AbcOptions, AbcResult};
+use admin_division::{DivisionEntry, DivisionNormalizer, DivisionOverrides};
+use cohort::{CohortOptions, CohortResult};
+use concentration::ConcentrationResult;
+use customer_lifecycle::{LifecycleCategory, LifecycleCustomer, LifecycleOptions, LifecycleResult};
+use customer_master::{CustomerMaster, CustomerMasterInfo};
+use dimension_ranking::{DimensionRankOptions, DimensionRankResult};
+use data_quality::{CodeNormalizationRules, DataQualityReport};
+use data_registry::{DataCache, DataRegistry, RegistryStatus, DEFAULT_MEMORY_BUDGET_MB};
+use excel_processor::{AnalysisResult, ProcessProgress, RankMetric, TopNOptions};
+use geography::{GeoDrillDownResult, GeoTreeResult};
+use job_manager::{JobInfo, JobManager, JobProgressEvent, JobStatus};
+use money::Money;
+use monthly_analysis::{MonthlyAnalysisResult, CustomerOption, TrendComparisonResult};
 use out_of_policy::{OutOfPolicyResult};
+use period::TrendOptions;
+use pivot::{PivotOptions, PivotResult};
+use rank_movers::{PeriodSpec, RankMoversOptions, RankMoversResult};
+use rfm::{RfmOptions, RfmResult};
+use row_filter::RowFilter;
+use std::future::Future;
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, State};
 use serde::{Deserialize, Serialize};
@@ -15,16 +52,93 @@ use calamine::Data;
 
 // 全局状态
 struct AppState {
+    jobs: Arc<JobManager>,
+    registry: Arc<DataRegistry>,
+    // 客户主数据（未导入时为 None）
+    master: Mutex<Option<Arc<CustomerMaster>>>,
+}
+
+impl AppState {
+    /// 当前的客户主数据
+    fn master(&self) -> Option<Arc<CustomerMaster>> {
+        self.master.lock().unwrap().clone()
+    }
+}
+
+/// 任务上下文：任务ID、专属取消标志以及进度上报
+#[derive(Clone)]
+struct JobContext {
+    job_id: String,
     cancel_flag: Arc<Mutex<bool>>,
-    data_cache: Arc<Mutex<Option<DataCache>>>,
+    jobs: Arc<JobManager>,
+    app: AppHandle,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct DataCache {
-    file_path: String,
-    cached_rows: Vec<CachedRow>,
-    // 用于前20大客户分析的缓存
-    customer_data_map: std::collections::HashMap<String, CustomerData>,
+impl JobContext {
+    /// 上报进度（事件中携带任务ID）
+    fn emit_progress(&self, progress: ProcessProgress) {
+        self.jobs.update_progress(&self.job_id, &progress);
+        let _ = self.app.emit("excel-progress", JobProgressEvent {
+            job_id: self.job_id.clone(),
+            progress,
+        });
+    }
+
+    /// 进度回调（用于传入各个处理函数）
+    fn progress_callback(&self) -> impl Fn(ProcessProgress) + Send + Sync {
+        let job = self.clone();
+        move |progress: ProcessProgress| job.emit_progress(progress)
+    }
+}
+
+/// 在后台启动任务，立即返回任务ID
+fn spawn_job<T, F, Fut>(app: &AppHandle, jobs: &Arc<JobManager>, kind: &str, task: F) -> String
+where
+    F: FnOnce(JobContext) -> Fut,
+    Fut: Future<Output = Result<T, String>> + Send + 'static,
+    T: Serialize + Send + 'static,
+{
+    let (job_id, cancel_flag) = jobs.create(kind);
+    let job = JobContext {
+        job_id: job_id.clone(),
+        cancel_flag,
+        jobs: jobs.clone(),
+        app: app.clone(),
+    };
+    let task = task(job);
+
+    let jobs = jobs.clone();
+    let app = app.clone();
+    let id = job_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = task.await;
+        jobs.finish(&id, &result);
+        if let Some(info) = jobs.get(&id) {
+            let _ = app.emit("job-finished", JobInfo { result: None, ..info });
+        }
+    });
+
+    job_id
+}
+
+/// 以任务方式执行并等待结果（用于需要直接返回结果的命令）
+async fn run_job<T, F, Fut>(app: &AppHandle, jobs: &Arc<JobManager>, kind: &str, task: F) -> Result<T, String>
+where
+    F: FnOnce(JobContext) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+    T: Serialize,
+{
+    let (job_id, cancel_flag) = jobs.create(kind);
+    let job = JobContext {
+        job_id: job_id.clone(),
+        cancel_flag,
+        jobs: jobs.clone(),
+        app: app.clone(),
+    };
+
+    let result = task(job).await;
+    jobs.finish(&job_id, &result);
+    result
 }
 
 /// 单个数据源配置
@@ -54,6 +168,7 @@ struct LoadOptionsResult {
     available_cities: Vec<String>,
     available_districts: Vec<String>,
     available_regions: Vec<String>,
+    available_custom_dimensions: std::collections::BTreeMap<String, Vec<String>>,
     total_rows: usize,
     load_time_ms: u128,
 }
@@ -66,6 +181,8 @@ struct DataSourceInfo {
     file_name: String,
     loaded_at: String,
     total_rows: usize,
+    // 是否已加载到内存
+    in_memory: bool,
 }
 
 /// 数据源列表信息
@@ -98,6 +215,50 @@ fn get_cache_path(data_source_id: &str) -> PathBuf {
     app_data_dir.join(format!("cache_{}.json", data_source_id))
 }
 
+/// 获取客户编码规范化规则文件路径
+fn get_code_rules_path() -> PathBuf {
+    let app_data_dir = get_app_data_dir();
+    std::fs::create_dir_all(&app_data_dir).unwrap_or_default();
+    app_data_dir.join("code_normalization.json")
+}
+
+/// 读取客户编码规范化规则（文件不存在或无法解析时使用默认规则）
+fn load_code_rules() -> CodeNormalizationRules {
+    fs::read_to_string(get_code_rules_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 获取地区名称自定义映射文件路径
+fn get_division_overrides_path() -> PathBuf {
+    let app_data_dir = get_app_data_dir();
+    std::fs::create_dir_all(&app_data_dir).unwrap_or_default();
+    app_data_dir.join("division_overrides.json")
+}
+
+/// 读取地区名称自定义映射（文件不存在或无法解析时为空）
+fn load_division_overrides() -> DivisionOverrides {
+    fs::read_to_string(get_division_overrides_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 获取客户主数据文件路径
+fn get_customer_master_path() -> PathBuf {
+    let app_data_dir = get_app_data_dir();
+    std::fs::create_dir_all(&app_data_dir).unwrap_or_default();
+    app_data_dir.join("customer_master.json")
+}
+
+/// 读取已保存的客户主数据（文件不存在或无法解析时为 None）
+fn load_customer_master() -> Option<CustomerMaster> {
+    fs::read_to_string(get_customer_master_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
 /// 保存数据源缓存到文件
 fn save_data_cache(data_source_id: &str, cache: &DataCache) -> Result<(), String> {
     let cache_path = get_cache_path(data_source_id);
@@ -119,8 +280,18 @@ fn load_data_cache(data_source_id: &str) -> Result<Option<DataCache>, String> {
     let content = fs::read_to_string(&cache_path)
         .map_err(|e| format!("读取缓存文件失败: {}", e))?;
     
-    let cache: DataCache = serde_json::from_str(&content)
+    let mut cache: DataCache = serde_json::from_str(&content)
         .map_err(|e| format!("解析缓存文件失败: {}", e))?;
+
+    // 旧版缓存没有日期列，按缓存不存在处理（重新解析Excel）
+    if !cache.store.has_date_column() {
+        return Ok(None);
+    }
+
+    // 旧版缓存没有预聚合立方体，重建后写回
+    if cache.ensure_cube() {
+        save_data_cache(data_source_id, &cache)?;
+    }
     
     Ok(Some(cache))
 }
@@ -135,52 +306,6 @@ fn delete_data_cache(data_source_id: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// 合并多个数据源的缓存
-fn merge_data_caches(data_source_ids: Vec<String>, _app: &AppHandle) -> Result<DataCache, String> {
-    if data_source_ids.is_empty() {
-        return Err("至少需要选择一个数据源".to_string());
-    }
-    
-    let mut merged_rows: Vec<CachedRow> = Vec::new();
-    let mut merged_customer_map: std::collections::HashMap<String, CustomerData> = 
-        std::collections::HashMap::new();
-    let mut merged_file_paths: Vec<String> = Vec::new();
-    
-    for id in &data_source_ids {
-        match load_data_cache(id)? {
-            Some(cache) => {
-                merged_file_paths.push(cache.file_path.clone());
-                merged_rows.extend(cache.cached_rows.clone());
-                
-                // 合并客户数据映射
-                for (code, customer) in cache.customer_data_map {
-                    merged_customer_map
-                        .entry(code.clone())
-                        .and_modify(|existing| {
-                            existing.pay_amount += customer.pay_amount;
-                            existing.recharge_deduction += customer.recharge_deduction;
-                            existing.total_amount += customer.total_amount;
-                            existing.order_count += customer.order_count;
-                            if existing.customer_name.is_empty() && !customer.customer_name.is_empty() {
-                                existing.customer_name = customer.customer_name.clone();
-                            }
-                        })
-                        .or_insert(customer);
-                }
-            },
-            None => {
-                return Err(format!("数据源 {} 的缓存不存在，请先加载该数据源", id));
-            }
-        }
-    }
-    
-    Ok(DataCache {
-        file_path: merged_file_paths.join("; "),
-        cached_rows: merged_rows,
-        customer_data_map: merged_customer_map,
-    })
-}
-
 /// 读取数据源列表配置
 fn load_data_source_list_config(app: &AppHandle) -> Result<DataSourceListConfig, String> {
     let config_path = get_config_path(app);
@@ -260,77 +385,221 @@ fn save_data_source_list_config(app: &AppHandle, config: &DataSourceListConfig)
     Ok(())
 }
 
-/// 添加数据源（从首页导入）
+/// 构建加载选项（客户取自预聚合立方体，省市区等取自列式存储的字典）
+///
+/// 有客户主数据时，客户列表按标准客户去重并使用标准名称。
+fn build_load_options(
+    cache: &DataCache,
+    master: Option<&CustomerMaster>,
+    file_name: String,
+    load_time_ms: u128,
+) -> LoadOptionsResult {
+    let available_customers: Vec<CustomerOption> = match master {
+        Some(master) => {
+            let mut customers: std::collections::BTreeMap<&str, &str> = std::collections::BTreeMap::new();
+            for (code, name) in &cache.cube.customer_names {
+                let canonical_code = master.canonical_code(code);
+                let name = master.canonical_name(code).unwrap_or(name);
+                let entry = customers.entry(canonical_code).or_insert(name);
+                if entry.is_empty() {
+                    *entry = name;
+                }
+            }
+            customers
+                .into_iter()
+                .map(|(code, name)| CustomerOption {
+                    code: code.to_string(),
+                    name: name.to_string(),
+                })
+                .collect()
+        },
+        None => cache.cube.customer_names
+            .iter()
+            .map(|(code, name)| CustomerOption {
+                code: code.clone(),
+                name: name.clone(),
+            })
+            .collect(),
+    };
+
+    LoadOptionsResult {
+        file_path: cache.file_path.clone(),
+        file_name,
+        available_customers,
+        available_provinces: cache.store.provinces.sorted_values(),
+        available_cities: cache.store.cities.sorted_values(),
+        available_districts: cache.store.districts.sorted_values(),
+        available_regions: cache.store.regions.sorted_values(),
+        available_custom_dimensions: cache.store.custom_dimension_values(),
+        total_rows: cache.store.len(),
+        load_time_ms,
+    }
+}
+
+/// 获取单个数据源（内存注册表 → 缓存文件 → 重新解析Excel）
+async fn get_source(
+    data_source_id: &str,
+    state: &AppState,
+    app: &AppHandle,
+) -> Result<Arc<DataCache>, String> {
+    let ids = [data_source_id.to_string()];
+    if let Some(cache) = state.registry.get(&ids) {
+        return Ok(cache);
+    }
+
+    let config = load_data_source_list_config(app)?;
+    let data_source = config.data_sources
+        .iter()
+        .find(|ds| ds.id == data_source_id)
+        .ok_or("数据源不存在")?;
+
+    // 尝试从文件加载缓存（持久化）
+    let cached_data = tokio::task::spawn_blocking({
+        let id = data_source_id.to_string();
+        move || load_data_cache(&id)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?;
+
+    if let Ok(Some(cached_data)) = cached_data {
+        // 验证文件路径是否匹配（防止文件被移动或重命名）
+        if cached_data.file_path == data_source.file_path {
+            return Ok(state.registry.insert(&ids, cached_data));
+        }
+    }
+
+    // 缓存不存在或文件路径不匹配，需要从Excel文件重新加载
+    let file_path = data_source.file_path.clone();
+
+    let code_rules = load_code_rules();
+    let divisions = DivisionNormalizer::new(&load_division_overrides());
+
+    let mut result = run_job(app, &state.jobs, "load_data_source", |job| async move {
+        let progress_callback = job.progress_callback();
+        tokio::task::spawn_blocking(move || {
+            monthly_analysis::load_excel_file(&file_path, &code_rules, &divisions, job.cancel_flag, progress_callback)
+        })
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?
+    })
+    .await?;
+
+    let cache_obj = DataCache::from_load_result(&mut result);
+
+    // 保存缓存到文件（持久化）
+    save_data_cache(data_source_id, &cache_obj)?;
+
+    Ok(state.registry.insert(&ids, cache_obj))
+}
+
+/// 获取多个数据源合并后的数据集（已合并的数据集会保留在注册表中复用）
+async fn get_dataset(
+    data_source_ids: &[String],
+    state: &AppState,
+    app: &AppHandle,
+) -> Result<Arc<DataCache>, String> {
+    let ids = DataRegistry::normalize_ids(data_source_ids);
+    if ids.is_empty() {
+        return Err("至少需要选择一个数据源".to_string());
+    }
+
+    if let Some(cache) = state.registry.get(&ids) {
+        return Ok(cache);
+    }
+
+    let mut sources: Vec<Arc<DataCache>> = Vec::with_capacity(ids.len());
+    for id in &ids {
+        sources.push(get_source(id, state, app).await?);
+    }
+
+    if sources.len() == 1 {
+        return Ok(sources.remove(0));
+    }
+
+    let merged = tokio::task::spawn_blocking(move || DataCache::merge(&sources))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?;
+
+    Ok(state.registry.insert(&ids, merged))
+}
+
+/// 展开筛选条件中的客户分组别名和 RFM 客户分群（需要计算 RFM 得分时在后台线程中进行）
+async fn resolve_filter(
+    data: &Arc<DataCache>,
+    filter: Option<RowFilter>,
+    master: Option<Arc<CustomerMaster>>,
+) -> Result<Option<RowFilter>, String> {
+    let Some(mut filter) = filter else {
+        return Ok(None);
+    };
+    if let Some(master) = &master {
+        filter.expand_customer_codes(master);
+    }
+    if filter.rfm.is_none() {
+        return Ok(Some(filter));
+    }
+
+    let data = Arc::clone(data);
+    tokio::task::spawn_blocking(move || rfm::resolve_filter(&data.store, Some(filter), master.as_deref()))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?
+}
+
+/// 数据源的文件名
+fn data_source_file_name(app: &AppHandle, data_source_id: &str) -> Result<String, String> {
+    let config = load_data_source_list_config(app)?;
+    config.data_sources
+        .iter()
+        .find(|ds| ds.id == data_source_id)
+        .map(|ds| ds.file_name.clone())
+        .ok_or_else(|| "数据源不存在".to_string())
+}
+
+/// 添加数据源（从首页导入），返回任务ID
 #[tauri::command]
 async fn add_data_source(
     file_path: String,
     state: State<'_, AppState>,
     app: AppHandle,
+) -> Result<String, String> {
+    let registry = state.registry.clone();
+    let master = state.master();
+
+    Ok(spawn_job(&app, &state.jobs, "add_data_source", move |job| {
+        import_data_source(file_path, registry, master, job)
+    }))
+}
+
+/// 导入数据源任务
+async fn import_data_source(
+    file_path: String,
+    registry: Arc<DataRegistry>,
+    master: Option<Arc<CustomerMaster>>,
+    job: JobContext,
 ) -> Result<LoadOptionsResult, String> {
-    {
-        let mut flag = state.cancel_flag.lock().unwrap();
-        *flag = false;
-    }
+    let app = job.app.clone();
 
     // 检查文件是否已存在
-    let mut config = load_data_source_list_config(&app)?;
+    let config = load_data_source_list_config(&app)?;
     if config.data_sources.iter().any(|ds| ds.file_path == file_path) {
         return Err("该文件已经添加为数据源".to_string());
     }
 
     // 加载并缓存数据
-    let cancel_flag = state.cancel_flag.clone();
-    let app_handle = app.clone();
-    let data_cache = state.data_cache.clone();
-
-    let progress_callback = move |progress: monthly_analysis::ProcessProgress| {
-        let _ = app_handle.emit("excel-progress", ProcessProgress {
-            step: progress.step,
-            message: progress.message,
-            percent: progress.percent,
-            detail: progress.detail,
-        });
-    };
+    let cancel_flag = job.cancel_flag.clone();
+    let progress_callback = job.progress_callback();
+    let code_rules = load_code_rules();
+    let divisions = DivisionNormalizer::new(&load_division_overrides());
 
-    let result = tokio::task::spawn_blocking({
+    let mut result = tokio::task::spawn_blocking({
         let file_path = file_path.clone();
         move || {
-            monthly_analysis::load_excel_file(&file_path, cancel_flag, progress_callback)
+            monthly_analysis::load_excel_file(&file_path, &code_rules, &divisions, cancel_flag, progress_callback)
         }
     })
     .await
     .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    // 构建客户数据映射（用于前20大客户分析）
-    // 预分配容量以提高性能
-    let estimated_customers = (result.cached_rows.len() / 10).max(100).min(10000);
-    let mut customer_data_map: std::collections::HashMap<String, CustomerData> = 
-        std::collections::HashMap::with_capacity(estimated_customers);
-    
-    // 优化：减少克隆操作，直接使用引用
-    for row in &result.cached_rows {
-        customer_data_map
-            .entry(row.customer_code.clone())
-            .and_modify(|existing| {
-                existing.pay_amount += row.pay_amount;
-                existing.recharge_deduction += row.recharge_deduction;
-                existing.total_amount += row.total_amount;
-                existing.order_count += 1;
-                // 只在需要时更新客户名称
-                if existing.customer_name.is_empty() && !row.customer_name.is_empty() {
-                    existing.customer_name.clone_from(&row.customer_name);
-                }
-            })
-            .or_insert_with(|| CustomerData {
-                customer_code: row.customer_code.clone(),
-                customer_name: row.customer_name.clone(),
-                pay_amount: row.pay_amount,
-                recharge_deduction: row.recharge_deduction,
-                total_amount: row.total_amount,
-                order_count: 1,
-            });
-    }
-
     // 添加到数据源列表
     let id = uuid::Uuid::new_v4().to_string();
     let file_name = std::path::Path::new(&result.file_path)
@@ -338,7 +607,22 @@ async fn add_data_source(
         .and_then(|n| n.to_str())
         .unwrap_or("未知文件")
         .to_string();
-    
+
+    // 创建缓存对象并保存到文件（持久化）
+    let cache_obj = DataCache::from_load_result(&mut result);
+    save_data_cache(&id, &cache_obj)?;
+
+    // 缓存到内存注册表
+    let cache = registry.insert(std::slice::from_ref(&id), cache_obj);
+
+    // 有客户主数据时客户列表按标准客户去重
+    let available_customers = match master.as_deref() {
+        Some(master) => build_load_options(&cache, Some(master), file_name.clone(), 0).available_customers,
+        None => result.available_customers,
+    };
+
+    // 重新读取配置，避免覆盖导入期间其他任务写入的数据源
+    let mut config = load_data_source_list_config(&app)?;
     config.data_sources.push(DataSourceConfig {
         id: id.clone(),
         file_path: result.file_path.clone(),
@@ -346,49 +630,32 @@ async fn add_data_source(
         loaded_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         total_rows: result.total_rows,
     });
-    
+
     // 设置为当前数据源
-    config.current_id = Some(id.clone());
-    
-    // 创建缓存对象
-    let cache_obj = DataCache {
-        file_path: result.file_path.clone(),
-        cached_rows: result.cached_rows.clone(),
-        customer_data_map,
-    };
-    
-    // 保存缓存到文件（持久化）
-    save_data_cache(&id, &cache_obj)?;
-    
-    // 缓存到内存
-    {
-        let mut cache = data_cache.lock().unwrap();
-        *cache = Some(cache_obj);
-    }
-    
-    // 保存配置
+    config.current_id = Some(id);
     save_data_source_list_config(&app, &config)?;
 
     Ok(LoadOptionsResult {
         file_path: result.file_path,
         file_name,
-        available_customers: result.available_customers,
+        available_customers,
         available_provinces: result.available_provinces,
         available_cities: result.available_cities,
         available_districts: result.available_districts,
         available_regions: result.available_regions,
+        available_custom_dimensions: result.available_custom_dimensions,
         total_rows: result.total_rows,
         load_time_ms: result.load_time_ms,
     })
 }
 
-/// 设置数据源（兼容旧接口）
+/// 设置数据源（兼容旧接口），返回任务ID
 #[tauri::command]
 async fn set_data_source(
     file_path: String,
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<LoadOptionsResult, String> {
+) -> Result<String, String> {
     add_data_source(file_path, state, app).await
 }
 
@@ -399,30 +666,33 @@ async fn get_data_source_list_info(
     state: State<'_, AppState>,
 ) -> Result<DataSourceListInfo, String> {
     let config = load_data_source_list_config(&app)?;
-    
-    // 检查缓存，更新total_rows
-    let cache = state.data_cache.lock().unwrap();
-    let cached_file_path = cache.as_ref().map(|c| &c.file_path);
-    
+
     let data_sources: Vec<DataSourceInfo> = config.data_sources
         .into_iter()
         .map(|ds| {
-            let total_rows = if cached_file_path == Some(&ds.file_path) {
-                cache.as_ref().map(|c| c.cached_rows.len()).unwrap_or(ds.total_rows)
+            // 已加载到内存的数据源使用实际缓存行数
+            let ids = [ds.id.clone()];
+            let in_memory = state.registry.contains_source(&ds.id);
+            let total_rows = if in_memory {
+                state.registry
+                    .get(&ids)
+                    .map(|c| c.store.len())
+                    .unwrap_or(ds.total_rows)
             } else {
                 ds.total_rows
             };
-            
+
             DataSourceInfo {
                 id: ds.id,
                 file_path: ds.file_path,
                 file_name: ds.file_name,
                 loaded_at: ds.loaded_at,
                 total_rows,
+                in_memory,
             }
         })
         .collect();
-    
+
     Ok(DataSourceListInfo {
         data_sources,
         current_id: config.current_id,
@@ -460,713 +730,782 @@ async fn delete_data_source(
 ) -> Result<(), String> {
     let mut config = load_data_source_list_config(&app)?;
     
-    // 检查是否是当前数据源
-    let is_current = config.current_id.as_ref() == Some(&data_source_id);
-    
-    // 删除数据源缓存文件
+    // 删除数据源缓存文件，并从内存注册表中移除（包括包含它的合并数据集）
     let _ = delete_data_cache(&data_source_id);
+    state.registry.remove_source(&data_source_id);
     
     // 删除数据源
     config.data_sources.retain(|ds| ds.id != data_source_id);
     
     // 如果删除的是当前数据源，切换到第一个（如果有）
-    if is_current {
+    if config.current_id.as_ref() == Some(&data_source_id) {
         config.current_id = config.data_sources.first().map(|ds| ds.id.clone());
-        
-        // 如果还有数据源，加载新的当前数据源
-        if let Some(new_current_id) = &config.current_id {
-            // 加载新数据源
-            let _ = load_data_source_by_id(new_current_id.clone(), state, app.clone()).await;
-        } else {
-            // 没有数据源了，清空缓存
-            let mut cache = state.data_cache.lock().unwrap();
-            *cache = None;
-        }
     }
     
     save_data_source_list_config(&app, &config)?;
     Ok(())
 }
 
-/// 切换数据源
+/// 切换当前数据源（仅记录页面默认选中的数据源，分析命令需显式指定数据源）
 #[tauri::command]
 async fn switch_data_source(
     data_source_id: String,
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<LoadOptionsResult, String> {
-    load_data_source_by_id(data_source_id, state, app).await
+    let cache = get_source(&data_source_id, &state, &app).await?;
+    let file_name = data_source_file_name(&app, &data_source_id)?;
+
+    // 更新配置中的当前数据源
+    let mut config = load_data_source_list_config(&app)?;
+    config.current_id = Some(data_source_id);
+    save_data_source_list_config(&app, &config)?;
+
+    Ok(build_load_options(&cache, state.master().as_deref(), file_name, 0))
 }
 
-/// 根据ID加载数据源
-async fn load_data_source_by_id(
-    data_source_id: String,
+/// 自动加载当前数据源（如果存在）
+#[tauri::command]
+async fn auto_load_data_source(
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<LoadOptionsResult, String> {
+) -> Result<Option<LoadOptionsResult>, String> {
     let config = load_data_source_list_config(&app)?;
     
-    let data_source = config.data_sources
-        .iter()
-        .find(|ds| ds.id == data_source_id)
-        .ok_or("数据源不存在")?;
-    
-    // 检查内存缓存是否已经是这个数据源
-    {
-        let cache = state.data_cache.lock().unwrap();
-        if let Some(ref data_cache) = *cache {
-            if data_cache.file_path == data_source.file_path {
-                // 已经是当前数据源，直接返回
-                let mut customers_map: std::collections::HashMap<String, String> = 
-                    std::collections::HashMap::new();
-                let mut provinces_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut cities_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut districts_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut regions_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-
-                for row in &data_cache.cached_rows {
-                    if !row.customer_code.is_empty() {
-                        customers_map.insert(row.customer_code.clone(), row.customer_name.clone());
-                    }
-                    if let Some(ref p) = row.province {
-                        if !p.is_empty() { provinces_set.insert(p.clone()); }
-                    }
-                    if let Some(ref c) = row.city {
-                        if !c.is_empty() { cities_set.insert(c.clone()); }
-                    }
-                    if let Some(ref d) = row.district {
-                        if !d.is_empty() { districts_set.insert(d.clone()); }
-                    }
-                    if let Some(ref r) = row.region {
-                        if !r.is_empty() { regions_set.insert(r.clone()); }
-                    }
-                }
-
-                let available_customers: Vec<CustomerOption> = customers_map
-                    .into_iter()
-                    .map(|(code, name)| CustomerOption { code, name })
-                    .collect();
-
-                let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-                available_provinces.sort();
-                let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-                available_cities.sort();
-                let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-                available_districts.sort();
-                let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-                available_regions.sort();
-
-                return Ok(LoadOptionsResult {
-                    file_path: data_source.file_path.clone(),
-                    file_name: data_source.file_name.clone(),
-                    available_customers,
-                    available_provinces,
-                    available_cities,
-                    available_districts,
-                    available_regions,
-                    total_rows: data_cache.cached_rows.len(),
-                    load_time_ms: 0,
-                });
-            }
-        }
-    }
-    
-    // 尝试从文件加载缓存（持久化）
-    if let Ok(Some(cached_data)) = load_data_cache(&data_source_id) {
-        // 验证文件路径是否匹配（防止文件被移动或重命名）
-        if cached_data.file_path == data_source.file_path {
-            // 从缓存构建返回结果
-            let mut customers_map: std::collections::HashMap<String, String> = 
-                std::collections::HashMap::new();
-            let mut provinces_set: std::collections::HashSet<String> = 
-                std::collections::HashSet::new();
-            let mut cities_set: std::collections::HashSet<String> = 
-                std::collections::HashSet::new();
-            let mut districts_set: std::collections::HashSet<String> = 
-                std::collections::HashSet::new();
-            let mut regions_set: std::collections::HashSet<String> = 
-                std::collections::HashSet::new();
-
-            for row in &cached_data.cached_rows {
-                if !row.customer_code.is_empty() {
-                    customers_map.insert(row.customer_code.clone(), row.customer_name.clone());
-                }
-                if let Some(ref p) = row.province {
-                    if !p.is_empty() { provinces_set.insert(p.clone()); }
-                }
-                if let Some(ref c) = row.city {
-                    if !c.is_empty() { cities_set.insert(c.clone()); }
-                }
-                if let Some(ref d) = row.district {
-                    if !d.is_empty() { districts_set.insert(d.clone()); }
-                }
-                if let Some(ref r) = row.region {
-                    if !r.is_empty() { regions_set.insert(r.clone()); }
-                }
-            }
+    let current_ds = match config.current_id {
+        Some(ref id) => config.data_sources
+            .iter()
+            .find(|ds| &ds.id == id)
+            .ok_or("当前数据源不存在")?,
+        // 如果没有当前数据源，尝试使用第一个
+        None => match config.data_sources.first() {
+            Some(first_ds) => first_ds,
+            None => return Ok(None),
+        },
+    };
 
-            let available_customers: Vec<CustomerOption> = customers_map
-                .into_iter()
-                .map(|(code, name)| CustomerOption { code, name })
-                .collect();
-
-            let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-            available_provinces.sort();
-            let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-            available_cities.sort();
-            let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-            available_districts.sort();
-            let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-            available_regions.sort();
-
-            // 保存总行数（在移动之前）
-            let total_rows = cached_data.cached_rows.len();
-
-            // 加载到内存缓存
-            {
-                let mut cache = state.data_cache.lock().unwrap();
-                *cache = Some(cached_data);
-            }
+    let cache = get_source(&current_ds.id, &state, &app).await?;
+    Ok(Some(build_load_options(&cache, state.master().as_deref(), current_ds.file_name.clone(), 0)))
+}
 
-            return Ok(LoadOptionsResult {
-                file_path: data_source.file_path.clone(),
-                file_name: data_source.file_name.clone(),
-                available_customers,
-                available_provinces,
-                available_cities,
-                available_districts,
-                available_regions,
-                total_rows,
-                load_time_ms: 0,
-            });
-        }
+/// 前N名客户排名
+///
+/// 没有筛选条件时直接使用预聚合立方体，否则按满足条件的行重新汇总；
+/// 有客户主数据时按标准客户合并后再排名。
+fn rank_top_n(
+    data: &DataCache,
+    options: &TopNOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> AnalysisResult {
+    let start_time = std::time::Instant::now();
+    let mut customers = match filter {
+        Some(filter) if !filter.is_empty() => row_filter::customer_totals(&data.store, filter),
+        _ => data.cube.customer_totals(),
+    };
+    if let Some(master) = master {
+        customers = master.merge_customers(customers);
     }
+    let mut result = excel_processor::rank_customers(customers, options, data.store.len());
+    result.process_time_ms = start_time.elapsed().as_millis();
+    result
+}
+
+/// 前N名客户分析（指定数据源），未传参数时为按总金额的前20名，可附加筛选条件
+#[tauri::command]
+async fn analyze_top20_cached(
+    data_source_id: String,
+    options: Option<TopNOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<AnalysisResult, String> {
+    let data = get_source(&data_source_id, &state, &app).await?;
+    let options = options.unwrap_or_default();
+    let master = state.master();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
     
-    // 缓存不存在或文件路径不匹配，需要从Excel文件重新加载
-    {
-        let mut flag = state.cancel_flag.lock().unwrap();
-        *flag = false;
-    }
+    tokio::task::spawn_blocking(move || {
+        Ok(rank_top_n(&data, &options, filter.as_ref(), master.as_deref()))
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    let cancel_flag = state.cancel_flag.clone();
-    let app_handle = app.clone();
-    let data_cache = state.data_cache.clone();
+/// 前N名客户分析（支持多数据源合并）
+#[tauri::command]
+async fn analyze_top20_multi(
+    data_source_ids: Vec<String>,
+    options: Option<TopNOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<AnalysisResult, String> {
+    // 合并多个数据源的缓存
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let options = options.unwrap_or_default();
+    let master = state.master();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+    
+    tokio::task::spawn_blocking(move || {
+        Ok(rank_top_n(&data, &options, filter.as_ref(), master.as_deref()))
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    let progress_callback = move |progress: monthly_analysis::ProcessProgress| {
-        let _ = app_handle.emit("excel-progress", ProcessProgress {
-            step: progress.step,
-            message: progress.message,
-            percent: progress.percent,
-            detail: progress.detail,
-        });
-    };
+/// 客户ABC分类（支持多数据源合并）
+#[tauri::command]
+async fn analyze_abc(
+    data_source_ids: Vec<String>,
+    options: Option<AbcOptions>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<AbcResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let options = options.unwrap_or_default();
+    let master = state.master();
 
-    let result = tokio::task::spawn_blocking({
-        let file_path = data_source.file_path.clone();
-        move || {
-            monthly_analysis::load_excel_file(&file_path, cancel_flag, progress_callback)
+    tokio::task::spawn_blocking(move || {
+        let mut customers = data.cube.customer_totals();
+        if let Some(master) = master.as_deref() {
+            customers = master.merge_customers(customers);
         }
+        abc_analysis::classify(customers, &options, data.store.len())
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    // 构建客户数据映射
-    let mut customer_data_map: std::collections::HashMap<String, CustomerData> = 
-        std::collections::HashMap::new();
-    
-    for row in &result.cached_rows {
-        customer_data_map
-            .entry(row.customer_code.clone())
-            .and_modify(|existing| {
-                existing.pay_amount += row.pay_amount;
-                existing.recharge_deduction += row.recharge_deduction;
-                existing.total_amount += row.total_amount;
-                existing.order_count += 1;
-                if existing.customer_name.is_empty() && !row.customer_name.is_empty() {
-                    existing.customer_name = row.customer_name.clone();
-                }
-            })
-            .or_insert_with(|| CustomerData {
-                customer_code: row.customer_code.clone(),
-                customer_name: row.customer_name.clone(),
-                pay_amount: row.pay_amount,
-                recharge_deduction: row.recharge_deduction,
-                total_amount: row.total_amount,
-                order_count: 1,
-            });
-    }
+/// 客户集中度分析（CR5/CR10/CR20、HHI、基尼系数），整体及按省份、地区、月份
+#[tauri::command]
+async fn analyze_concentration(
+    data_source_ids: Vec<String>,
+    metric: Option<RankMetric>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<ConcentrationResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let metric = metric.unwrap_or_default();
+    let master = state.master();
 
-    // 创建缓存对象
-    let cache_obj = DataCache {
-        file_path: result.file_path.clone(),
-        cached_rows: result.cached_rows.clone(),
-        customer_data_map,
-    };
-    
-    // 保存缓存到文件（持久化）
-    save_data_cache(&data_source_id, &cache_obj)?;
-    
-    // 更新内存缓存
-    {
-        let mut cache = data_cache.lock().unwrap();
-        *cache = Some(cache_obj);
-    }
+    tokio::task::spawn_blocking(move || {
+        concentration::analyze(&data.cube, &data.store, metric, master.as_deref())
+    })
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))
+}
 
-    // 更新配置中的当前数据源
-    let mut config = load_data_source_list_config(&app)?;
-    config.current_id = Some(data_source_id.clone());
-    save_data_source_list_config(&app, &config)?;
+/// 按维度排行（省份、城市、区县、地区或自定义维度），可指定上级维度下钻
+#[tauri::command]
+async fn analyze_dimension_ranking(
+    data_source_ids: Vec<String>,
+    options: DimensionRankOptions,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<DimensionRankResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
 
-    let file_name = std::path::Path::new(&result.file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("未知文件")
-        .to_string();
+    tokio::task::spawn_blocking(move || dimension_ranking::rank(&data.store, &options, master.as_deref()))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    Ok(LoadOptionsResult {
-        file_path: result.file_path,
-        file_name,
-        available_customers: result.available_customers,
-        available_provinces: result.available_provinces,
-        available_cities: result.available_cities,
-        available_districts: result.available_districts,
-        available_regions: result.available_regions,
-        total_rows: result.total_rows,
-        load_time_ms: result.load_time_ms,
+/// 两个期间（不同月份区间或不同数据源）的客户排名变化
+#[tauri::command]
+async fn analyze_rank_movers(
+    base: PeriodSpec,
+    compare: PeriodSpec,
+    options: Option<RankMoversOptions>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<RankMoversResult, String> {
+    let base_data = get_dataset(&base.data_source_ids, &state, &app).await?;
+    let compare_data = get_dataset(&compare.data_source_ids, &state, &app).await?;
+    let options = options.unwrap_or_default();
+    let master = state.master();
+
+    tokio::task::spawn_blocking(move || {
+        rank_movers::compare(&base_data, &base, &compare_data, &compare, &options, master.as_deref())
     })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))
 }
 
-/// 自动加载数据源（如果存在）
+/// 地理树：省份 → 城市 → 区县，每个节点包含金额、订单数和客户数
 #[tauri::command]
-async fn auto_load_data_source(
+async fn get_geography_tree(
+    data_source_ids: Vec<String>,
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<Option<LoadOptionsResult>, String> {
-    let config = load_data_source_list_config(&app)?;
-    
-    let current_id = match config.current_id {
-        Some(id) => id,
-        None => {
-            // 如果没有当前数据源，尝试使用第一个
-            if let Some(first_ds) = config.data_sources.first() {
-                first_ds.id.clone()
-            } else {
-                return Ok(None);
-            }
-        },
-    };
-    
-    let current_ds = config.data_sources
-        .iter()
-        .find(|ds| ds.id == current_id)
-        .ok_or("当前数据源不存在")?;
-
-    // 如果缓存已存在且文件路径匹配，直接返回
-    {
-        let cache = state.data_cache.lock().unwrap();
-        if let Some(ref data_cache) = *cache {
-            if data_cache.file_path == current_ds.file_path {
-                // 从缓存构建返回结果
-                let mut customers_map: std::collections::HashMap<String, String> = 
-                    std::collections::HashMap::new();
-                let mut provinces_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut cities_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut districts_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut regions_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-
-                for row in &data_cache.cached_rows {
-                    if !row.customer_code.is_empty() {
-                        customers_map.insert(row.customer_code.clone(), row.customer_name.clone());
-                    }
-                    if let Some(ref p) = row.province {
-                        if !p.is_empty() { provinces_set.insert(p.clone()); }
-                    }
-                    if let Some(ref c) = row.city {
-                        if !c.is_empty() { cities_set.insert(c.clone()); }
-                    }
-                    if let Some(ref d) = row.district {
-                        if !d.is_empty() { districts_set.insert(d.clone()); }
-                    }
-                    if let Some(ref r) = row.region {
-                        if !r.is_empty() { regions_set.insert(r.clone()); }
-                    }
-                }
+) -> Result<GeoTreeResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
 
-                let available_customers: Vec<CustomerOption> = customers_map
-                    .into_iter()
-                    .map(|(code, name)| CustomerOption { code, name })
-                    .collect();
-
-                let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-                available_provinces.sort();
-                let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-                available_cities.sort();
-                let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-                available_districts.sort();
-                let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-                available_regions.sort();
-
-                return Ok(Some(LoadOptionsResult {
-                    file_path: current_ds.file_path.clone(),
-                    file_name: current_ds.file_name.clone(),
-                    available_customers,
-                    available_provinces,
-                    available_cities,
-                    available_districts,
-                    available_regions,
-                    total_rows: data_cache.cached_rows.len(),
-                    load_time_ms: 0,
-                }));
-            }
-        }
-    }
+    tokio::task::spawn_blocking(move || geography::build_tree(&data.store, master.as_deref()))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))
+}
+
+/// 地理下钻：返回指定节点（空路径为全国）的下级节点及其趋势
+#[tauri::command]
+async fn drill_down_geography(
+    data_source_ids: Vec<String>,
+    path: Vec<String>,
+    options: Option<TrendOptions>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<GeoDrillDownResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let options = options.unwrap_or_default();
+    let master = state.master();
 
-    // 缓存不存在，需要重新加载
-    let result = load_data_source_by_id(current_id, state, app).await?;
-    Ok(Some(result))
+    tokio::task::spawn_blocking(move || {
+        geography::drill_down(&data.store, &path, &options, master.as_deref())
+    })
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?
 }
 
-/// 前20大客户分析（使用缓存数据）
+/// 客户留存：按首购周期划分同期群，可按地区等条件筛选
 #[tauri::command]
-async fn analyze_top20_cached(
+async fn analyze_cohorts(
+    data_source_ids: Vec<String>,
+    options: Option<CohortOptions>,
+    filter: Option<RowFilter>,
     state: State<'_, AppState>,
-) -> Result<AnalysisResult, String> {
-    let data_cache = state.data_cache.clone();
-    
-    let result = tokio::task::spawn_blocking(move || {
-        let cache = data_cache.lock().unwrap();
-        
-        match cache.as_ref() {
-            Some(data) => {
-                let mut customers: Vec<CustomerData> = 
-                    data.customer_data_map.values().cloned().collect();
-                
-                // 排序
-                customers.sort_by(|a, b| {
-                    b.total_amount
-                        .partial_cmp(&a.total_amount)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                });
-                
-                let total_amount: f64 = customers.iter().map(|c| c.total_amount).sum();
-                let top20: Vec<CustomerData> = customers.iter().take(20).cloned().collect();
-                let top20_amount: f64 = top20.iter().map(|c| c.total_amount).sum();
-                
-                Ok(AnalysisResult {
-                    top20,
-                    total_customers: customers.len(),
-                    total_amount,
-                    top20_amount,
-                    total_rows: data.cached_rows.len(),
-                    process_time_ms: 0,
-                })
-            },
-            None => Err("请先在首页导入数据源".to_string()),
-        }
+    app: AppHandle,
+) -> Result<CohortResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+
+    tokio::task::spawn_blocking(move || {
+        cohort::analyze(&data.store, &options, filter.as_ref(), master.as_deref())
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    Ok(result)
+/// 客户生命周期：每个周期的新客户、回头客户、召回客户和流失客户
+#[tauri::command]
+async fn analyze_customer_lifecycle(
+    data_source_ids: Vec<String>,
+    options: Option<LifecycleOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<LifecycleResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+
+    tokio::task::spawn_blocking(move || {
+        customer_lifecycle::analyze(&data.store, &options, filter.as_ref(), master.as_deref())
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
 }
 
-/// 前20大客户分析（支持多数据源合并）
+/// 列出某周期某生命周期分类下的客户
 #[tauri::command]
-async fn analyze_top20_multi(
+async fn get_lifecycle_customers(
     data_source_ids: Vec<String>,
-    _state: State<'_, AppState>,
+    options: Option<LifecycleOptions>,
+    filter: Option<RowFilter>,
+    period: String,
+    category: LifecycleCategory,
+    state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<AnalysisResult, String> {
-    // 合并多个数据源的缓存
-    let merged_cache = merge_data_caches(data_source_ids, &app)?;
-    
-    let result = tokio::task::spawn_blocking(move || -> Result<AnalysisResult, String> {
-        let mut customers: Vec<CustomerData> = 
-            merged_cache.customer_data_map.values().cloned().collect();
-        
-        // 排序
-        customers.sort_by(|a, b| {
-            b.total_amount
-                .partial_cmp(&a.total_amount)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        
-        let total_amount: f64 = customers.iter().map(|c| c.total_amount).sum();
-        let top20: Vec<CustomerData> = customers.iter().take(20).cloned().collect();
-        let top20_amount: f64 = top20.iter().map(|c| c.total_amount).sum();
-        
-        Ok(AnalysisResult {
-            top20,
-            total_customers: customers.len(),
-            total_amount,
-            top20_amount,
-            total_rows: merged_cache.cached_rows.len(),
-            process_time_ms: 0,
-        })
+) -> Result<Vec<LifecycleCustomer>, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+
+    tokio::task::spawn_blocking(move || {
+        customer_lifecycle::customers(&data.store, &options, filter.as_ref(), master.as_deref(), &period, category)
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    Ok(result)
+/// RFM 客户价值分析：最近购买、购买次数、购买金额得分及客户分群
+#[tauri::command]
+async fn analyze_rfm(
+    data_source_ids: Vec<String>,
+    options: Option<RfmOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<RfmResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let mut filter = filter;
+    if let (Some(filter), Some(master)) = (&mut filter, &master) {
+        filter.expand_customer_codes(master);
+    }
+
+    tokio::task::spawn_blocking(move || {
+        rfm::analyze(&data.store, &options, filter.as_ref(), master.as_deref())
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
 }
 
+/// 计算 RFM 得分并导出为Excel文件，返回保存路径
 #[tauri::command]
-async fn analyze_excel(
+async fn export_rfm(
+    data_source_ids: Vec<String>,
+    options: Option<RfmOptions>,
+    filter: Option<RowFilter>,
     file_path: String,
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<AnalysisResult, String> {
-    {
-        let mut flag = state.cancel_flag.lock().unwrap();
-        *flag = false;
+) -> Result<String, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let mut filter = filter;
+    if let (Some(filter), Some(master)) = (&mut filter, &master) {
+        filter.expand_customer_codes(master);
     }
 
-    let cancel_flag = state.cancel_flag.clone();
-    let app_handle = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = rfm::analyze(&data.store, &options, filter.as_ref(), master.as_deref())?;
+        rfm::export_xlsx(&result, &file_path)?;
+        Ok(file_path)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    let progress_callback = move |progress: ProcessProgress| {
-        let _ = app_handle.emit("excel-progress", &progress);
-    };
+/// 透视表：行维度 × 列维度（或周期），可附加筛选条件和小计
+#[tauri::command]
+async fn analyze_pivot(
+    data_source_ids: Vec<String>,
+    options: Option<PivotOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<PivotResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
 
-    let result = tokio::task::spawn_blocking(move || {
-        excel_processor::process_excel_file(&file_path, cancel_flag, progress_callback)
+    tokio::task::spawn_blocking(move || {
+        pivot::build(&data, &options, filter.as_ref(), master.as_deref())
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))?;
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    result
+/// 生成透视表并导出为Excel文件，返回保存路径
+#[tauri::command]
+async fn export_pivot(
+    data_source_ids: Vec<String>,
+    options: Option<PivotOptions>,
+    filter: Option<RowFilter>,
+    file_path: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let result = pivot::build(&data, &options, filter.as_ref(), master.as_deref())?;
+        pivot::export_xlsx(&result, &file_path)?;
+        Ok(file_path)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
+
+/// 直接分析Excel文件，返回任务ID
+#[tauri::command]
+async fn analyze_excel(
+    file_path: String,
+    options: Option<TopNOptions>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+
+    Ok(spawn_job(&app, &state.jobs, "analyze_excel", move |job| async move {
+        let progress_callback = job.progress_callback();
+        let cancel_flag = job.cancel_flag;
+        let code_rules = load_code_rules();
+
+        tokio::task::spawn_blocking(move || {
+            excel_processor::process_excel_file(&file_path, &options, &code_rules, cancel_flag, progress_callback)
+        })
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))?
+    }))
 }
 
-/// 加载Excel文件并缓存数据（保留兼容性）
+/// 加载Excel文件并缓存数据（保留兼容性），返回任务ID
 #[tauri::command]
 async fn load_monthly_file(
     file_path: String,
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<LoadOptionsResult, String> {
+) -> Result<String, String> {
     set_data_source(file_path, state, app).await
 }
 
-/// 基于缓存数据执行月度分析
+/// 基于指定数据源执行趋势分析（默认按月，可指定粒度和财年起始月份，可附加筛选条件）
 #[tauri::command]
 async fn analyze_monthly_cached(
+    data_source_id: String,
     analysis_type: String,
     target: String,
+    options: Option<TrendOptions>,
+    filter: Option<RowFilter>,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<MonthlyAnalysisResult, String> {
-    let data_cache = state.data_cache.clone();
+    let data = get_source(&data_source_id, &state, &app).await?;
     
-    let result = tokio::task::spawn_blocking(move || {
-        let cache = data_cache.lock().unwrap();
-        
-        match cache.as_ref() {
-            Some(data) => {
-                monthly_analysis::analyze_from_cache(
-                    &data.cached_rows, 
-                    &analysis_type, 
-                    &target
-                )
-            },
-            None => Err("请先在首页导入数据源".to_string()),
-        }
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
+    
+    tokio::task::spawn_blocking(move || {
+        monthly_analysis::analyze_from_cache(
+            &data.cube, 
+            &data.store,
+            &analysis_type, 
+            &target,
+            master.as_deref(),
+            &options,
+            filter.as_ref(),
+        )
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
-
-    Ok(result)
+    .map_err(|e| format!("任务执行失败: {}", e))?
 }
 
-/// 基于合并的多个数据源执行月度分析
+/// 基于合并的多个数据源执行趋势分析
 #[tauri::command]
 async fn analyze_monthly_multi(
     data_source_ids: Vec<String>,
     analysis_type: String,
     target: String,
+    options: Option<TrendOptions>,
+    filter: Option<RowFilter>,
+    state: State<'_, AppState>,
     app: AppHandle,
 ) -> Result<MonthlyAnalysisResult, String> {
     // 合并多个数据源的缓存
-    let merged_cache = merge_data_caches(data_source_ids, &app)?;
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    
+    let master = state.master();
+    let options = options.unwrap_or_default();
+    let filter = resolve_filter(&data, filter, master.clone()).await?;
     
-    let result = tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(move || {
         monthly_analysis::analyze_from_cache(
-            &merged_cache.cached_rows, 
+            &data.cube, 
+            &data.store,
             &analysis_type, 
-            &target
+            &target,
+            master.as_deref(),
+            &options,
+            filter.as_ref(),
         )
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
 
-    Ok(result)
+/// 多个目标（客户、省份等）的趋势对比，返回周期 × 目标矩阵
+#[tauri::command]
+async fn analyze_trend_comparison(
+    data_source_ids: Vec<String>,
+    analysis_type: String,
+    targets: Vec<String>,
+    options: Option<TrendOptions>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<TrendComparisonResult, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        monthly_analysis::compare_targets(
+            &data.cube,
+            &data.store,
+            &analysis_type,
+            &targets,
+            master.as_deref(),
+            &options,
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
 }
 
 /// 获取合并后的月度分析选项
 #[tauri::command]
 async fn get_monthly_options_multi(
     data_source_ids: Vec<String>,
+    state: State<'_, AppState>,
     app: AppHandle,
 ) -> Result<LoadOptionsResult, String> {
     // 合并多个数据源的缓存
-    let merged_cache = merge_data_caches(data_source_ids, &app)?;
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
     
-    // 构建选项
-    let mut customers_map: std::collections::HashMap<String, String> = 
-        std::collections::HashMap::new();
-    let mut provinces_set: std::collections::HashSet<String> = 
-        std::collections::HashSet::new();
-    let mut cities_set: std::collections::HashSet<String> = 
-        std::collections::HashSet::new();
-    let mut districts_set: std::collections::HashSet<String> = 
-        std::collections::HashSet::new();
-    let mut regions_set: std::collections::HashSet<String> = 
-        std::collections::HashSet::new();
-
-    for row in &merged_cache.cached_rows {
-        if !row.customer_code.is_empty() {
-            customers_map.insert(row.customer_code.clone(), row.customer_name.clone());
-        }
-        if let Some(ref p) = row.province {
-            if !p.is_empty() { provinces_set.insert(p.clone()); }
-        }
-        if let Some(ref c) = row.city {
-            if !c.is_empty() { cities_set.insert(c.clone()); }
-        }
-        if let Some(ref d) = row.district {
-            if !d.is_empty() { districts_set.insert(d.clone()); }
-        }
-        if let Some(ref r) = row.region {
-            if !r.is_empty() { regions_set.insert(r.clone()); }
-        }
+    tokio::task::spawn_blocking(move || {
+        build_load_options(&data, master.as_deref(), "合并数据源".to_string(), 0)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))
+}
+
+/// 获取指定数据源的月度分析选项
+#[tauri::command]
+async fn get_monthly_options(
+    data_source_id: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<LoadOptionsResult, String> {
+    let data = get_source(&data_source_id, &state, &app).await?;
+    let file_name = data_source_file_name(&app, &data_source_id)?;
+    let master = state.master();
+    
+    tokio::task::spawn_blocking(move || build_load_options(&data, master.as_deref(), file_name, 0))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))
+}
+
+/// 获取客户编码规范化规则
+#[tauri::command]
+fn get_code_normalization_rules() -> CodeNormalizationRules {
+    load_code_rules()
+}
+
+/// 设置客户编码规范化规则
+///
+/// 规则只在导入时生效，因此会清除内存和磁盘上的缓存，下次使用时按新规则重新解析。
+#[tauri::command]
+fn set_code_normalization_rules(
+    rules: CodeNormalizationRules,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&rules)
+        .map_err(|e| format!("序列化规则失败: {}", e))?;
+    fs::write(get_code_rules_path(), json)
+        .map_err(|e| format!("保存规则失败: {}", e))?;
+
+    let config = load_data_source_list_config(&app)?;
+    for data_source in &config.data_sources {
+        let _ = delete_data_cache(&data_source.id);
     }
+    state.registry.clear();
 
-    let available_customers: Vec<CustomerOption> = customers_map
-        .into_iter()
-        .map(|(code, name)| CustomerOption { code, name })
-        .collect();
+    Ok(())
+}
 
-    let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-    available_provinces.sort();
-    let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-    available_cities.sort();
-    let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-    available_districts.sort();
-    let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-    available_regions.sort();
+/// 获取内置行政区词典（省级、地级名称及 GB/T 2260 代码）
+///
+/// 词典不含区县，区县名称只去除空白并应用自定义映射，不做校验。
+#[tauri::command]
+fn get_division_dictionary() -> Vec<DivisionEntry> {
+    admin_division::dictionary()
+}
 
-    Ok(LoadOptionsResult {
-        file_path: merged_cache.file_path,
-        file_name: "合并数据源".to_string(),
-        available_customers,
-        available_provinces,
-        available_cities,
-        available_districts,
-        available_regions,
-        total_rows: merged_cache.cached_rows.len(),
-        load_time_ms: 0,
-    })
+/// 获取地区名称自定义映射
+#[tauri::command]
+fn get_division_overrides() -> DivisionOverrides {
+    load_division_overrides()
 }
 
-/// 获取月度分析的选项（从缓存）
+/// 设置地区名称自定义映射
+///
+/// 与编码规范化规则一样只在导入时生效，因此会清除缓存，下次使用时重新解析。
 #[tauri::command]
-async fn get_monthly_options(
+fn set_division_overrides(
+    overrides: DivisionOverrides,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<Option<LoadOptionsResult>, String> {
-    let data_cache = state.data_cache.clone();
-    
-    let result: Option<LoadOptionsResult> = tokio::task::spawn_blocking(move || {
-        let cache = data_cache.lock().unwrap();
-        
-        match cache.as_ref() {
-            Some(data) => {
-                let mut customers_map: std::collections::HashMap<String, String> = 
-                    std::collections::HashMap::new();
-                let mut provinces_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut cities_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut districts_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-                let mut regions_set: std::collections::HashSet<String> = 
-                    std::collections::HashSet::new();
-
-                for row in &data.cached_rows {
-                    if !row.customer_code.is_empty() {
-                        customers_map.insert(row.customer_code.clone(), row.customer_name.clone());
-                    }
-                    if let Some(ref p) = row.province {
-                        if !p.is_empty() { provinces_set.insert(p.clone()); }
-                    }
-                    if let Some(ref c) = row.city {
-                        if !c.is_empty() { cities_set.insert(c.clone()); }
-                    }
-                    if let Some(ref d) = row.district {
-                        if !d.is_empty() { districts_set.insert(d.clone()); }
-                    }
-                    if let Some(ref r) = row.region {
-                        if !r.is_empty() { regions_set.insert(r.clone()); }
-                    }
-                }
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&overrides)
+        .map_err(|e| format!("序列化地区映射失败: {}", e))?;
+    fs::write(get_division_overrides_path(), json)
+        .map_err(|e| format!("保存地区映射失败: {}", e))?;
 
-                let available_customers: Vec<CustomerOption> = customers_map
-                    .into_iter()
-                    .map(|(code, name)| CustomerOption { code, name })
-                    .collect();
-
-                let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-                available_provinces.sort();
-                let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-                available_cities.sort();
-                let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-                available_districts.sort();
-                let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-                available_regions.sort();
-
-                let file_name = std::path::Path::new(&data.file_path)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("未知文件")
-                    .to_string();
-
-                Some(LoadOptionsResult {
-                    file_path: data.file_path.clone(),
-                    file_name,
-                    available_customers,
-                    available_provinces,
-                    available_cities,
-                    available_districts,
-                    available_regions,
-                    total_rows: data.cached_rows.len(),
-                    load_time_ms: 0,
-                })
-            },
-            None => None,
-        }
+    let config = load_data_source_list_config(&app)?;
+    for data_source in &config.data_sources {
+        let _ = delete_data_cache(&data_source.id);
+    }
+    state.registry.clear();
+
+    Ok(())
+}
+
+/// 数据质量报告：同一编码对应多个名称、规范化后冲突的编码、无法匹配的省份和城市
+///
+/// 区县没有内置词典，不检查是否能匹配，报告中只给出未检查的区县取值数。
+#[tauri::command]
+async fn get_data_quality_report(
+    data_source_ids: Vec<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<DataQualityReport, String> {
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let rules = load_code_rules();
+    let divisions = DivisionNormalizer::new(&load_division_overrides());
+
+    tokio::task::spawn_blocking(move || data_quality::build_report(&data.store, &rules, &divisions))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))
+}
+
+/// 导入客户主数据（编码映射、标准名称、客户性质、业务员）
+#[tauri::command]
+async fn import_customer_master(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<CustomerMasterInfo, String> {
+    let code_rules = load_code_rules();
+
+    let master = tokio::task::spawn_blocking(move || -> Result<CustomerMaster, String> {
+        let master = customer_master::load_master_workbook(&file_path, &code_rules)?;
+        let json = serde_json::to_string_pretty(&master)
+            .map_err(|e| format!("序列化客户主数据失败: {}", e))?;
+        fs::write(get_customer_master_path(), json)
+            .map_err(|e| format!("保存客户主数据失败: {}", e))?;
+        Ok(master)
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))?;
+    .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    Ok(result)
+    let info = master.info();
+    *state.master.lock().unwrap() = Some(Arc::new(master));
+    Ok(info)
 }
 
-/// 清除缓存
+/// 获取当前客户主数据概况（未导入时为 None）
+#[tauri::command]
+fn get_customer_master_info(state: State<'_, AppState>) -> Option<CustomerMasterInfo> {
+    state.master().map(|master| master.info())
+}
+
+/// 清除客户主数据
+#[tauri::command]
+fn clear_customer_master(state: State<'_, AppState>) -> Result<(), String> {
+    let path = get_customer_master_path();
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| format!("删除客户主数据失败: {}", e))?;
+    }
+    *state.master.lock().unwrap() = None;
+    Ok(())
+}
+
+/// 数据中存在但客户主数据中没有的客户（按总金额从高到低）
+#[tauri::command]
+async fn get_unmapped_customers(
+    data_source_ids: Vec<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Vec<excel_processor::CustomerData>, String> {
+    let master = state.master().ok_or("尚未导入客户主数据")?;
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+
+    tokio::task::spawn_blocking(move || master.unmapped_customers(data.cube.customer_totals()))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))
+}
+
+/// 清除内存中的所有数据源缓存
 #[tauri::command]
 fn clear_data_cache(state: State<'_, AppState>) {
-    let mut cache = state.data_cache.lock().unwrap();
-    *cache = None;
+    state.registry.clear();
+}
+
+/// 获取内存注册表状态
+#[tauri::command]
+fn get_registry_status(state: State<'_, AppState>) -> RegistryStatus {
+    state.registry.status()
+}
+
+/// 设置内存预算（MB）
+#[tauri::command]
+fn set_memory_budget(
+    budget_mb: usize,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if budget_mb == 0 {
+        return Err("内存预算必须大于0".to_string());
+    }
+    state.registry.set_budget(budget_mb * 1024 * 1024);
+    Ok(())
 }
 
+/// 取消所有正在运行的任务（兼容旧接口）
 #[tauri::command]
 fn cancel_analysis(state: State<'_, AppState>) {
-    let mut flag = state.cancel_flag.lock().unwrap();
-    *flag = true;
+    state.jobs.cancel_all();
+}
+
+/// 获取任务列表
+#[tauri::command]
+fn list_jobs(state: State<'_, AppState>) -> Vec<JobInfo> {
+    state.jobs.list()
+}
+
+/// 获取任务详情（含结果或错误）
+#[tauri::command]
+fn get_job(
+    job_id: String,
+    state: State<'_, AppState>,
+) -> Result<JobInfo, String> {
+    state.jobs.get(&job_id).ok_or_else(|| "任务不存在".to_string())
+}
+
+/// 取消单个任务
+#[tauri::command]
+fn cancel_job(
+    job_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.jobs.cancel(&job_id)
+}
+
+/// 删除已结束的任务
+#[tauri::command]
+fn remove_job(
+    job_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.jobs.remove(&job_id)
+}
+
+/// 等待任务结束，成功时返回任务结果
+#[tauri::command]
+async fn wait_job(
+    job_id: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let info = state.jobs.wait(&job_id).await?;
+
+    match info.status {
+        JobStatus::Completed => Ok(info.result.unwrap_or(serde_json::Value::Null)),
+        _ => Err(info.error.unwrap_or_else(|| "任务执行失败".to_string())),
+    }
 }
 
 /// 保存导出文件
@@ -1202,50 +1541,44 @@ async fn save_excel_file(
 /// 获取订单明细（用于导出）
 #[tauri::command]
 async fn get_order_details(
+    data_source_ids: Vec<String>,
     analysis_type: String,
     target: String,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<Vec<monthly_analysis::CachedRow>, String> {
-    let data_cache = state.data_cache.clone();
+    let data = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
     
-    let result = tokio::task::spawn_blocking(move || {
-        let cache = data_cache.lock().unwrap();
+    tokio::task::spawn_blocking(move || {
+        // 有客户主数据时包含同一标准客户下所有编码的明细
+        let codes = match master.as_deref() {
+            Some(master) if analysis_type == "customer" => master.codes_for(&target),
+            _ => vec![target.as_str()],
+        };
+
+        let mut rows: Vec<usize> = codes
+            .iter()
+            .flat_map(|code| data.store.matching_rows(&analysis_type, code))
+            .collect();
+        rows.sort_unstable();
+
+        let details: Vec<monthly_analysis::CachedRow> = rows
+            .into_iter()
+            .map(|i| data.store.row(i))
+            .collect();
         
-        match cache.as_ref() {
-            Some(data) => {
-                let mut details: Vec<monthly_analysis::CachedRow> = Vec::new();
-                
-                for row in &data.cached_rows {
-                    let matches = match analysis_type.as_str() {
-                        "customer" => row.customer_code == target,
-                        "province" => row.province.as_ref().map_or(false, |p| p == &target),
-                        "city" => row.city.as_ref().map_or(false, |c| c == &target),
-                        "district" => row.district.as_ref().map_or(false, |d| d == &target),
-                        "region" => row.region.as_ref().map_or(false, |r| r == &target),
-                        _ => false,
-                    };
-                    
-                    if matches {
-                        details.push(row.clone());
-                    }
-                }
-                
-                Ok(details)
-            },
-            None => Err("请先在首页导入数据源".to_string()),
-        }
+        details
     })
     .await
-    .map_err(|e| format!("任务执行失败: {}", e))??;
-
-    Ok(result)
+    .map_err(|e| format!("任务执行失败: {}", e))
 }
 
 /// 客户采购额月度数据
 #[derive(Debug, Serialize, Deserialize)]
 struct CustomerMonthlyPurchase {
     month: String,
-    total_amount: f64,
+    total_amount: Money,
 }
 
 /// 客户采购额数据
@@ -1254,7 +1587,7 @@ struct CustomerPurchaseData {
     customer_code: String,
     customer_name: String,
     monthly_data: Vec<CustomerMonthlyPurchase>,
-    total_amount: f64,
+    total_amount: Money,
 }
 
 /// 客户采购额计算结果
@@ -1262,7 +1595,7 @@ struct CustomerPurchaseData {
 struct CustomerPurchaseResult {
     customer_data: Vec<CustomerPurchaseData>,
     total_customers: usize,
-    total_amount: f64,
+    total_amount: Money,
 }
 
 /// 加载客户编码Excel文件（返回完整数据）
@@ -1384,25 +1717,42 @@ async fn load_out_of_policy_excel(
 async fn calculate_customer_purchase(
     data_source_ids: Vec<String>,
     customer_codes: Vec<String>,
+    state: State<'_, AppState>,
     app: AppHandle,
 ) -> Result<CustomerPurchaseResult, String> {
     // 合并多个数据源的缓存
-    let merged_cache = merge_data_caches(data_source_ids, &app)?;
+    let merged_cache = get_dataset(&data_source_ids, &state, &app).await?;
+    let master = state.master();
     
     let result = tokio::task::spawn_blocking(move || -> Result<CustomerPurchaseResult, String> {
-        // 创建客户编码集合用于快速查找
+        let master = master.as_deref();
+        let canonical_code = |code: &str| -> String {
+            match master {
+                Some(master) => master.canonical_code(code).to_string(),
+                None => code.to_string(),
+            }
+        };
+
+        // 创建客户编码集合用于快速查找（有客户主数据时按标准编码匹配）
         let customer_code_set: std::collections::HashSet<String> = 
-            customer_codes.iter().cloned().collect();
+            customer_codes.iter().map(|code| canonical_code(code)).collect();
         
         // 按客户编码和月份分组统计
         let mut customer_map: std::collections::HashMap<String, CustomerPurchaseData> = 
             std::collections::HashMap::new();
         
-        for row in &merged_cache.cached_rows {
+        let store = &merged_cache.store;
+        for i in 0..store.len() {
             // 只处理在客户编码列表中的客户
-            if !customer_code_set.contains(&row.customer_code) {
+            let customer_code = canonical_code(store.customer_code(i));
+            if !customer_code_set.contains(&customer_code) {
                 continue;
             }
+            let mut row = store.row(i);
+            if let Some(name) = master.and_then(|master| master.canonical_name(&customer_code)) {
+                row.customer_name = name.to_string();
+            }
+            row.customer_code = customer_code;
             
             let month = row.month.clone().unwrap_or_else(|| "未知月份".to_string());
             
@@ -1456,7 +1806,7 @@ async fn calculate_customer_purchase(
         }
         
         let total_customers = customer_data.len();
-        let total_amount: f64 = customer_data.iter().map(|c| c.total_amount).sum();
+        let total_amount: Money = customer_data.iter().map(|c| c.total_amount).sum();
         
         Ok(CustomerPurchaseResult {
             customer_data,
@@ -1476,8 +1826,9 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .manage(AppState {
-            cancel_flag: Arc::new(Mutex::new(false)),
-            data_cache: Arc::new(Mutex::new(None)),
+            jobs: Arc::new(JobManager::new()),
+            registry: Arc::new(DataRegistry::new(DEFAULT_MEMORY_BUDGET_MB * 1024 * 1024)),
+            master: Mutex::new(load_customer_master().map(Arc::new)),
         })
         .invoke_handler(tauri::generate_handler![
             analyze_excel,
@@ -1490,13 +1841,44 @@ pub fn run() {
             auto_load_data_source,
             analyze_top20_cached,
             analyze_top20_multi,
+            analyze_abc,
+            analyze_concentration,
+            analyze_dimension_ranking,
+            analyze_rank_movers,
             load_monthly_file,
             get_monthly_options,
             get_monthly_options_multi,
             analyze_monthly_cached,
             analyze_monthly_multi,
+            analyze_trend_comparison,
+            analyze_pivot,
+            get_geography_tree,
+            drill_down_geography,
+            analyze_cohorts,
+            analyze_customer_lifecycle,
+            get_lifecycle_customers,
+            analyze_rfm,
+            export_rfm,
+            export_pivot,
             clear_data_cache,
+            get_code_normalization_rules,
+            set_code_normalization_rules,
+            get_division_dictionary,
+            get_division_overrides,
+            set_division_overrides,
+            get_data_quality_report,
+            import_customer_master,
+            get_customer_master_info,
+            clear_customer_master,
+            get_unmapped_customers,
+            get_registry_status,
+            set_memory_budget,
             cancel_analysis,
+            list_jobs,
+            get_job,
+            cancel_job,
+            remove_job,
+            wait_job,
             save_export_file,
             save_excel_file,
             get_order_details,
@@ -0,0 +1,140 @@
+use calamine::Data;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// 金额的最小单位：0.0001 元
+const SCALE: i64 = 10_000;
+
+/// 定点金额（以 0.0001 元为单位的整数）
+///
+/// 解析和汇总全部使用整数运算，结果与相加顺序（包括并行分块的顺序）无关；
+/// 只在序列化给前端时转换为元（浮点数），由前端负责四舍五入显示。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// 由浮点数（元）转换，四舍五入到 0.0001 元
+    pub fn from_yuan(yuan: f64) -> Self {
+        if yuan.is_finite() {
+            Money((yuan * SCALE as f64).round() as i64)
+        } else {
+            Money::ZERO
+        }
+    }
+
+    /// 转换为元（用于展示和比例计算）
+    pub fn to_yuan(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// 按十进制字符串精确解析，支持千分位逗号和货币符号，超出4位的小数四舍五入
+    pub fn parse(text: &str) -> Option<Self> {
+        let cleaned: String = text
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ',' | '，' | '¥' | '￥' | ' '))
+            .collect();
+
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        if integer.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        if !integer.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            // 科学计数法等格式按浮点数处理
+            return cleaned.parse::<f64>().ok().map(Money::from_yuan);
+        }
+
+        let mut units: i64 = if integer.is_empty() { 0 } else { integer.parse().ok()? };
+        units = units.checked_mul(SCALE)?;
+
+        let mut scale = SCALE / 10;
+        let mut fraction_digits = fraction.bytes();
+        for digit in fraction_digits.by_ref().take(4) {
+            units = units.checked_add(((digit - b'0') as i64).checked_mul(scale)?)?;
+            scale /= 10;
+        }
+        if fraction_digits.next().is_some_and(|digit| digit >= b'5') {
+            units = units.checked_add(1)?;
+        }
+
+        Some(Money(if negative { -units } else { units }))
+    }
+
+    /// 由Excel单元格解析，无法识别时为0
+    pub fn from_data(value: &Data) -> Self {
+        match value {
+            Data::Float(f) => Money::from_yuan(*f),
+            Data::Int(i) => Money(i.saturating_mul(SCALE)),
+            Data::String(s) => Money::parse(s).unwrap_or_default(),
+            _ => Money::ZERO,
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_yuan())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from_yuan)
+    }
+}
@@ -1,8 +1,40 @@
-use calamine::{open_workbook, Reader, Xlsx, Xls, Data};
+use calamine::{open_workbook, Reader, Xlsx, Xls, Data, DataRef};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use regex_lite::Regex;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub use crate::excel_processor::ProcessProgress;
+use crate::excel_processor::percent_of;
+use crate::admin_division::DivisionNormalizer;
+use crate::aggregate_cube::{AggregateCube, CubeCell};
+use crate::columnar_store::ColumnarStore;
+use crate::customer_master::CustomerMaster;
+use crate::data_quality::CodeNormalizationRules;
+use crate::money::Money;
+use crate::period::{parse_year_month, Period, TrendOptions};
+use crate::row_filter::RowFilter;
+
+/// 作为自定义维度保存的分类列（存在于表头时才读取）
+pub const CUSTOM_DIMENSION_COLUMNS: [&str; 8] = [
+    "订单类型",
+    "所属主店",
+    "管理机构",
+    "客户性质",
+    "销售方式",
+    "销售类型",
+    "支付方式",
+    "业务员",
+];
+
+/// 自定义维度的分析类型前缀，例如 "custom:客户性质"
+pub const CUSTOM_DIMENSION_PREFIX: &str = "custom:";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomerOption {
@@ -10,39 +42,42 @@ pub struct CustomerOption {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ProcessProgress {
-    pub step: String,
-    pub message: String,
-    pub percent: u32,
-    pub detail: String,
-}
 
-/// 缓存的行数据
+/// 单行数据（解析与导出订单明细时使用，缓存中以列式存储保存）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedRow {
     pub customer_code: String,
     pub customer_name: String,
-    pub pay_amount: f64,
-    pub recharge_deduction: f64,
-    pub total_amount: f64,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
+    pub total_amount: Money,
     pub province: Option<String>,
     pub city: Option<String>,
     pub district: Option<String>,
     pub region: Option<String>,
     pub month: Option<String>,    // 格式 "2024-01"
+    // 订单日期，格式 "2024-01-15"（日期列只有年月时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    // 自定义维度：列名 → 值（只包含非空值）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, String>,
 }
 
 /// 文件加载结果
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileLoadResult {
     pub file_path: String,
-    pub cached_rows: Vec<CachedRow>,
+    pub store: ColumnarStore,
+    // 导入时构建的预聚合立方体
+    pub cube: AggregateCube,
     pub available_customers: Vec<CustomerOption>,
     pub available_provinces: Vec<String>,
     pub available_cities: Vec<String>,
     pub available_districts: Vec<String>,
     pub available_regions: Vec<String>,
+    // 自定义维度：列名 → 可选值
+    pub available_custom_dimensions: BTreeMap<String, Vec<String>>,
     pub total_rows: usize,
     pub load_time_ms: u128,
 }
@@ -50,12 +85,20 @@ pub struct FileLoadResult {
 /// 月度销售数据
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MonthlySalesData {
+    // 周期名称（按月时为 "2024-01"，其他粒度见 TrendOptions::label）
     pub month: String,
-    pub total_amount: f64,
-    pub pay_amount: f64,
-    pub recharge_deduction: f64,
+    pub total_amount: Money,
+    pub pay_amount: Money,
+    pub recharge_deduction: Money,
     pub order_count: u32,
-    pub mom_growth_rate: f64,  // 环比增长率
+    pub mom_growth_rate: f64,  // 环比增长率（与上一个周期相比）
+    // 去年同月金额与同比增长率（数据未覆盖去年同月时为 None）
+    pub last_year_amount: Option<Money>,
+    pub yoy_growth_rate: Option<f64>,
+    // 本年截至当月的累计金额，以及去年同期累计与增长率
+    pub ytd_amount: Money,
+    pub last_year_ytd_amount: Option<Money>,
+    pub ytd_growth_rate: Option<f64>,
 }
 
 /// 月度分析结果
@@ -64,15 +107,60 @@ pub struct MonthlyAnalysisResult {
     pub analysis_type: String,
     pub target: String,
     pub target_name: String,
+    pub options: TrendOptions,
+    // 数据范围内连续的周期（没有销售的周期金额为0）
     pub monthly_data: Vec<MonthlySalesData>,
-    pub total_amount: f64,
+    // 没有日期（或按日、按周时只有年月）的行单独汇总，不参与环比和同比
+    pub unknown_month: Option<MonthlySalesData>,
+    pub total_amount: Money,
+    pub total_orders: u32,
+    // 截至最后一个周期的本年累计及同比
+    pub ytd_amount: Money,
+    pub last_year_ytd_amount: Option<Money>,
+    pub ytd_growth_rate: Option<f64>,
+    pub process_time_ms: u128,
+}
+
+/// 多目标对比中的一个目标
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComparisonTarget {
+    pub target: String,
+    pub target_name: String,
+    pub total_amount: Money,
     pub total_orders: u32,
+    // 占所有目标合计的比例（%）
+    pub share: f64,
+    // 逐周期数据（含环比、同比），与 periods 对齐
+    pub data: Vec<MonthlySalesData>,
+}
+
+/// 对比矩阵中的一行（一个周期）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComparisonPeriod {
+    pub period: String,
+    // 各目标的金额与占比，顺序与 targets 一致
+    pub amounts: Vec<Money>,
+    pub shares: Vec<f64>,
+    pub total: Money,
+}
+
+/// 多目标趋势对比结果（周期 × 目标矩阵）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendComparisonResult {
+    pub analysis_type: String,
+    pub options: TrendOptions,
+    pub targets: Vec<ComparisonTarget>,
+    // 最后一行为未知周期（如果有）
+    pub periods: Vec<ComparisonPeriod>,
+    pub total_amount: Money,
     pub process_time_ms: u128,
 }
 
 /// 加载Excel文件并缓存数据
 pub fn load_excel_file<F>(
     file_path: &str,
+    code_rules: &CodeNormalizationRules,
+    divisions: &DivisionNormalizer,
     cancel_flag: Arc<Mutex<bool>>,
     progress_callback: F,
 ) -> Result<FileLoadResult, String>
@@ -93,23 +181,33 @@ where
         message: "正在打开Excel文件...".to_string(),
         percent: 5,
         detail: format!("文件: {}", path.file_name().unwrap_or_default().to_string_lossy()),
+        ..Default::default()
     });
 
     if *cancel_flag.lock().unwrap() {
         return Err("用户取消操作".to_string());
     }
 
-    // 读取Excel数据
-    let rows: Vec<Vec<Data>> = match extension.as_str() {
-        "xlsx" => {
-            let mut workbook: Xlsx<_> = open_workbook(file_path)
+    // 读取并逐块解析Excel数据（第一行为表头）
+    let mut parser = ChunkedParser::new(code_rules, divisions);
+    match extension.as_str() {
+        "xlsx" => read_xlsx_rows_streaming(file_path, &cancel_flag, &progress_callback, |rows| {
+            if *cancel_flag.lock().unwrap() {
+                return Err("用户取消操作".to_string());
+            }
+            parser.push(rows)
+        })?,
+        // xls 格式行数有限（最多65536行），直接整表读取
+        "xls" => {
+            let mut workbook: Xls<_> = open_workbook(file_path)
                 .map_err(|e| format!("无法打开Excel文件: {}", e))?;
             
             progress_callback(ProcessProgress {
-                step: "1/3".to_string(),
+                step: "2/3".to_string(),
                 message: "正在解析Excel数据...".to_string(),
                 percent: 15,
                 detail: "读取工作表中...".to_string(),
+                ..Default::default()
             });
 
             let sheet_name = workbook.sheet_names().first()
@@ -117,129 +215,57 @@ where
             
             let range = workbook.worksheet_range(&sheet_name)
                 .map_err(|e| format!("无法读取工作表: {}", e))?;
-            
-            range.rows().map(|r| r.to_vec()).collect()
-        },
-        "xls" => {
-            let mut workbook: Xls<_> = open_workbook(file_path)
-                .map_err(|e| format!("无法打开Excel文件: {}", e))?;
-            
+            let rows: Vec<Vec<Data>> = range.rows().map(|r| r.to_vec()).collect();
+
+            if *cancel_flag.lock().unwrap() {
+                return Err("用户取消操作".to_string());
+            }
             progress_callback(ProcessProgress {
-                step: "1/3".to_string(),
-                message: "正在解析Excel数据...".to_string(),
-                percent: 15,
-                detail: "读取工作表中...".to_string(),
+                step: "2/3".to_string(),
+                message: "正在解析数据...".to_string(),
+                percent: 40,
+                detail: format!("共 {} 行数据", rows.len().saturating_sub(1)),
+                ..Default::default()
             });
 
-            let sheet_name = workbook.sheet_names().first()
-                .ok_or("Excel文件没有工作表")?.clone();
-            
-            let range = workbook.worksheet_range(&sheet_name)
-                .map_err(|e| format!("无法读取工作表: {}", e))?;
-            
-            range.rows().map(|r| r.to_vec()).collect()
+            parser.push(&rows)?;
         },
         _ => return Err(format!("不支持的文件格式: {}", extension)),
     };
 
-    if rows.is_empty() {
-        return Err("Excel文件为空".to_string());
-    }
-
-    let total_rows = rows.len() - 1;
-    
-    progress_callback(ProcessProgress {
-        step: "2/3".to_string(),
-        message: "正在解析数据...".to_string(),
-        percent: 40,
-        detail: format!("共 {} 行数据", total_rows),
-    });
-
     if *cancel_flag.lock().unwrap() {
         return Err("用户取消操作".to_string());
     }
 
-    // 解析表头
-    let header = &rows[0];
-    let col_indices = find_column_indices(header)?;
-
-    // 解析所有行并缓存
-    // 预分配容量以提高性能
-    let mut cached_rows: Vec<CachedRow> = Vec::with_capacity(total_rows);
-    
-    // 预分配集合容量
-    let estimated_unique = (total_rows / 20).max(50).min(5000);
-    let mut customers_map: HashMap<String, String> = HashMap::with_capacity(estimated_unique);
-    let mut provinces_set: std::collections::HashSet<String> = std::collections::HashSet::with_capacity(estimated_unique.min(100));
-    let mut cities_set: std::collections::HashSet<String> = std::collections::HashSet::with_capacity(estimated_unique.min(500));
-    let mut districts_set: std::collections::HashSet<String> = std::collections::HashSet::with_capacity(estimated_unique.min(1000));
-    let mut regions_set: std::collections::HashSet<String> = std::collections::HashSet::with_capacity(estimated_unique.min(100));
-
-    let data_rows: Vec<_> = rows.iter().skip(1).collect();
-    let row_count = data_rows.len();
-    
-    // 优化：批量处理，减少进度回调频率
-    let progress_interval = if row_count > 50000 { 20000 } else if row_count > 10000 { 5000 } else { 1000 };
-    
-    for (i, row) in data_rows.iter().enumerate() {
-        if let Some(parsed) = parse_row(row, &col_indices) {
-            // 收集选项 - 优化：减少不必要的克隆
-            if !parsed.customer_code.is_empty() {
-                customers_map.entry(parsed.customer_code.clone())
-                    .or_insert_with(|| parsed.customer_name.clone());
-            }
-            if let Some(ref p) = parsed.province {
-                if !p.is_empty() { provinces_set.insert(p.clone()); }
-            }
-            if let Some(ref c) = parsed.city {
-                if !c.is_empty() { cities_set.insert(c.clone()); }
-            }
-            if let Some(ref d) = parsed.district {
-                if !d.is_empty() { districts_set.insert(d.clone()); }
-            }
-            if let Some(ref r) = parsed.region {
-                if !r.is_empty() { regions_set.insert(r.clone()); }
-            }
-            
-            cached_rows.push(parsed);
-        }
-        
-        // 优化：根据数据量调整进度更新频率
-        if i % progress_interval == 0 || i == row_count - 1 {
-            let percent = 40 + ((i as f64 / row_count as f64) * 50.0) as u32;
-            progress_callback(ProcessProgress {
-                step: "2/3".to_string(),
-                message: "正在解析数据...".to_string(),
-                percent,
-                detail: format!("已处理 {} / {} 行", i + 1, row_count),
-            });
-        }
-    }
+    let (merged, total_rows) = parser.finish()?;
 
     progress_callback(ProcessProgress {
         step: "3/3".to_string(),
         message: "正在整理数据...".to_string(),
         percent: 95,
-        detail: "生成选项列表...".to_string(),
+        detail: format!("共 {} 行数据，生成选项列表...", total_rows),
+        ..Default::default()
     });
 
+    let store = merged.store;
+
+    // 构建预聚合立方体（维度 × 维度值 × 月份）
+    let cube = AggregateCube::build(&store);
+
     // 整理选项
-    let available_customers: Vec<CustomerOption> = customers_map
-        .into_iter()
-        .map(|(code, name)| CustomerOption { code, name })
+    let available_customers: Vec<CustomerOption> = cube.customer_names
+        .iter()
+        .map(|(code, name)| CustomerOption {
+            code: code.clone(),
+            name: name.clone(),
+        })
         .collect();
     
-    let mut available_provinces: Vec<String> = provinces_set.into_iter().collect();
-    available_provinces.sort();
-    
-    let mut available_cities: Vec<String> = cities_set.into_iter().collect();
-    available_cities.sort();
-    
-    let mut available_districts: Vec<String> = districts_set.into_iter().collect();
-    available_districts.sort();
-    
-    let mut available_regions: Vec<String> = regions_set.into_iter().collect();
-    available_regions.sort();
+    let available_provinces = store.provinces.sorted_values();
+    let available_cities = store.cities.sorted_values();
+    let available_districts = store.districts.sorted_values();
+    let available_regions = store.regions.sorted_values();
+    let available_custom_dimensions = store.custom_dimension_values();
 
     let load_time_ms = start_time.elapsed().as_millis();
 
@@ -247,98 +273,378 @@ where
         step: "完成".to_string(),
         message: "数据加载完成！".to_string(),
         percent: 100,
-        detail: format!("耗时 {}ms，缓存 {} 行数据", load_time_ms, cached_rows.len()),
+        detail: format!("耗时 {}ms，缓存 {} 行数据", load_time_ms, store.len()),
+        ..Default::default()
     });
 
     Ok(FileLoadResult {
         file_path: file_path.to_string(),
-        cached_rows,
+        store,
+        cube,
         available_customers,
         available_provinces,
         available_cities,
         available_districts,
         available_regions,
+        available_custom_dimensions,
         total_rows,
         load_time_ms,
     })
 }
 
-/// 基于缓存数据进行月度分析
-pub fn analyze_from_cache(
-    cached_rows: &[CachedRow],
-    analysis_type: &str,
-    target: &str,
-) -> Result<MonthlyAnalysisResult, String> {
-    let start_time = std::time::Instant::now();
-    
-    if target.is_empty() {
-        return Err("请选择分析目标".to_string());
+/// 分块解析的行数：流式读取时每读满一块即解析并释放原始行，整表不会驻留内存
+const PARSE_CHUNK_ROWS: usize = 50_000;
+
+/// 逐块解析数据行（收到的第一行为表头），按原始顺序合并结果
+struct ChunkedParser<'a> {
+    code_rules: &'a CodeNormalizationRules,
+    divisions: &'a DivisionNormalizer,
+    indices: Option<ColumnIndices>,
+    merged: PartialLoad,
+    data_rows: usize,
+}
+
+impl<'a> ChunkedParser<'a> {
+    fn new(code_rules: &'a CodeNormalizationRules, divisions: &'a DivisionNormalizer) -> Self {
+        ChunkedParser {
+            code_rules,
+            divisions,
+            indices: None,
+            merged: PartialLoad::with_capacity(0),
+            data_rows: 0,
+        }
     }
 
-    // 按月份汇总
-    let mut monthly_map: HashMap<String, MonthlySalesData> = HashMap::new();
-    let mut target_name = String::new();
-    
-    for row in cached_rows {
-        let matches = match analysis_type {
-            "customer" => {
-                if row.customer_code == target {
-                    if target_name.is_empty() && !row.customer_name.is_empty() {
-                        target_name = row.customer_name.clone();
-                    }
-                    true
-                } else {
-                    false
-                }
+    /// 解析一块行数据（使用Rayon并行）
+    fn push(&mut self, rows: &[Vec<Data>]) -> Result<(), String> {
+        let rows = match &self.indices {
+            Some(_) => rows,
+            None => {
+                let Some((header, rest)) = rows.split_first() else {
+                    return Ok(());
+                };
+                self.indices = Some(find_column_indices(header)?);
+                rest
             },
-            "province" => row.province.as_ref().map_or(false, |p| p == target),
-            "city" => row.city.as_ref().map_or(false, |c| c == target),
-            "district" => row.district.as_ref().map_or(false, |d| d == target),
-            "region" => row.region.as_ref().map_or(false, |r| r == target),
-            _ => false,
         };
+        let Some(indices) = &self.indices else {
+            return Ok(());
+        };
+        let (code_rules, divisions) = (self.code_rules, self.divisions);
 
-        if matches {
-            let month = row.month.clone().unwrap_or_else(|| "未知月份".to_string());
-            
-            monthly_map
-                .entry(month.clone())
-                .and_modify(|data| {
-                    data.total_amount += row.total_amount;
-                    data.pay_amount += row.pay_amount;
-                    data.recharge_deduction += row.recharge_deduction;
-                    data.order_count += 1;
-                })
-                .or_insert(MonthlySalesData {
-                    month,
-                    total_amount: row.total_amount,
-                    pay_amount: row.pay_amount,
-                    recharge_deduction: row.recharge_deduction,
-                    order_count: 1,
-                    mom_growth_rate: 0.0,
-                });
+        // 子块数多于线程数，使负载更均衡
+        let chunk_size = (rows.len() / (rayon::current_num_threads().max(1) * 4)).max(1000);
+        let partials: Vec<PartialLoad> = rows
+            .par_chunks(chunk_size)
+            .map(|chunk| {
+                let mut partial = PartialLoad::with_capacity(chunk.len());
+                for row in chunk {
+                    if let Some(parsed) = parse_row(row, indices, code_rules, divisions) {
+                        partial.add(parsed);
+                    }
+                }
+                partial
+            })
+            .collect();
+
+        self.data_rows += rows.len();
+        for partial in partials {
+            self.merged.merge(partial);
+        }
+        Ok(())
+    }
+
+    /// 解析结果及数据行数（不含表头）
+    fn finish(self) -> Result<(PartialLoad, usize), String> {
+        if self.indices.is_none() {
+            return Err("Excel文件为空".to_string());
         }
+        Ok((self.merged, self.data_rows))
     }
+}
 
-    // 按月份排序
-    let mut monthly_data: Vec<MonthlySalesData> = monthly_map.into_values().collect();
-    monthly_data.sort_by(|a, b| a.month.cmp(&b.month));
+/// 统计已读取字节数的读取器，用于流式读取时上报进度
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Arc<AtomicU64>,
+}
 
-    // 计算环比增长率
-    for i in 0..monthly_data.len() {
-        if i > 0 {
-            let prev_amount = monthly_data[i - 1].total_amount;
-            let curr_amount = monthly_data[i].total_amount;
-            if prev_amount > 0.0 {
-                monthly_data[i].mom_growth_rate = ((curr_amount - prev_amount) / prev_amount) * 100.0;
-            } else if curr_amount > 0.0 {
-                monthly_data[i].mom_growth_rate = 100.0;
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// 单个分块的解析结果
+struct PartialLoad {
+    store: ColumnarStore,
+}
+
+impl PartialLoad {
+    fn with_capacity(rows: usize) -> Self {
+        PartialLoad {
+            store: ColumnarStore::with_capacity(rows),
+        }
+    }
+
+    /// 加入一行
+    fn add(&mut self, row: CachedRow) {
+        self.store.push(&row);
+    }
+
+    /// 合并另一个分块（追加在当前分块之后）
+    fn merge(&mut self, other: PartialLoad) {
+        self.store.append(&other.store);
+    }
+}
+
+/// 流式读取xlsx第一个工作表的所有非空行，每读满 PARSE_CHUNK_ROWS 行交给 on_chunk 处理
+///
+/// 逐个单元格读取，边读边上报已读字节数、行数、吞吐量和预计剩余时间，
+/// 并定期检查取消标志，避免大文件在读取阶段无法中断。
+fn read_xlsx_rows_streaming<F, C>(
+    file_path: &str,
+    cancel_flag: &Arc<Mutex<bool>>,
+    progress_callback: &F,
+    mut on_chunk: C,
+) -> Result<(), String>
+where
+    F: Fn(ProcessProgress),
+    C: FnMut(&[Vec<Data>]) -> Result<(), String>,
+{
+    // 取消检查与进度上报的间隔
+    const CANCEL_CHECK_ROWS: u64 = 1000;
+    const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+    let file = File::open(file_path).map_err(|e| format!("无法打开Excel文件: {}", e))?;
+    let total_bytes = file.metadata().ok().map(|m| m.len());
+    let bytes_read = Arc::new(AtomicU64::new(0));
+    let mut workbook: Xlsx<_> = Xlsx::new(CountingReader {
+        inner: BufReader::new(file),
+        bytes_read: bytes_read.clone(),
+    })
+    .map_err(|e| format!("无法打开Excel文件: {}", e))?;
+
+    if *cancel_flag.lock().unwrap() {
+        return Err("用户取消操作".to_string());
+    }
+
+    let sheet_name = workbook.sheet_names().first()
+        .ok_or("Excel文件没有工作表")?.clone();
+
+    let mut reader = workbook.worksheet_cells_reader(&sheet_name)
+        .map_err(|e| format!("无法读取工作表: {}", e))?;
+
+    // 根据工作表声明的范围估算总行数（部分文件可能没有声明）
+    let dimensions = reader.dimensions();
+    let estimated_total_rows = if dimensions.end.0 > dimensions.start.0 {
+        Some((dimensions.end.0 - dimensions.start.0 + 1) as u64)
+    } else {
+        None
+    };
+
+    let read_start = Instant::now();
+    let progress = |rows_read: u64, estimated_total_rows: Option<u64>| StreamingProgress {
+        rows_read,
+        estimated_total_rows,
+        bytes_read: bytes_read.load(Ordering::Relaxed),
+        total_bytes,
+        elapsed: read_start.elapsed(),
+    };
+    progress_callback(progress(0, estimated_total_rows).into());
+
+    let mut chunk: Vec<Vec<Data>> = Vec::with_capacity(PARSE_CHUNK_ROWS);
+    let mut current: Vec<Data> = Vec::new();
+    let mut current_row: Option<u32> = None;
+    let mut rows_read: u64 = 0;
+    let mut last_report = read_start;
+
+    while let Some(cell) = reader.next_cell().map_err(|e| format!("读取单元格失败: {}", e))? {
+        let value = cell.get_value();
+        // 与 worksheet_range 保持一致：忽略空单元格，全空的行不会出现在结果中
+        if *value == DataRef::Empty {
+            continue;
+        }
+
+        let (row, col) = cell.get_position();
+        if current_row != Some(row) {
+            if current_row.is_some() {
+                chunk.push(std::mem::take(&mut current));
+                rows_read += 1;
+
+                if chunk.len() >= PARSE_CHUNK_ROWS {
+                    on_chunk(&chunk)?;
+                    chunk.clear();
+                }
+
+                if rows_read.is_multiple_of(CANCEL_CHECK_ROWS) {
+                    if *cancel_flag.lock().unwrap() {
+                        return Err("用户取消操作".to_string());
+                    }
+
+                    if last_report.elapsed() >= PROGRESS_INTERVAL {
+                        last_report = Instant::now();
+                        progress_callback(progress(rows_read, estimated_total_rows).into());
+                    }
+                }
             }
+            current_row = Some(row);
+        }
+
+        let col = col as usize;
+        if current.len() <= col {
+            current.resize(col + 1, Data::Empty);
         }
+        current[col] = Data::from(value.clone());
     }
 
-    let total_amount: f64 = monthly_data.iter().map(|d| d.total_amount).sum();
-    let total_orders: u32 = monthly_data.iter().map(|d| d.order_count).sum();
+    if current_row.is_some() {
+        chunk.push(current);
+        rows_read += 1;
+    }
+    if !chunk.is_empty() {
+        on_chunk(&chunk)?;
+    }
+
+    if *cancel_flag.lock().unwrap() {
+        return Err("用户取消操作".to_string());
+    }
+
+    progress_callback(progress(rows_read, Some(rows_read)).into());
+
+    Ok(())
+}
+
+/// 流式读取阶段的统计信息
+struct StreamingProgress {
+    rows_read: u64,
+    estimated_total_rows: Option<u64>,
+    bytes_read: u64,
+    total_bytes: Option<u64>,
+    elapsed: Duration,
+}
+
+/// 读取并解析阶段的进度（15% ~ 90%），包含吞吐量和预计剩余时间
+///
+/// 能估算总行数时按行数计算进度，否则按已读取的字节数。
+impl From<StreamingProgress> for ProcessProgress {
+    fn from(progress: StreamingProgress) -> Self {
+        let StreamingProgress { rows_read, estimated_total_rows, bytes_read, total_bytes, elapsed } = progress;
+        let secs = elapsed.as_secs_f64();
+        let rows_per_second = if secs > 0.0 { Some(rows_read as f64 / secs) } else { None };
+
+        let eta_seconds = match (estimated_total_rows, rows_per_second) {
+            (Some(total), Some(rate)) if rate > 0.0 => {
+                Some(total.saturating_sub(rows_read) as f64 / rate)
+            },
+            _ => None,
+        };
+
+        let fraction = match (estimated_total_rows, total_bytes) {
+            (Some(total), _) if total > 0 => rows_read.min(total) as f64 / total as f64,
+            (_, Some(total)) if total > 0 => bytes_read.min(total) as f64 / total as f64,
+            _ => 0.0,
+        };
+
+        let mut detail = match estimated_total_rows {
+            Some(total) => format!("已读取 {} / 约 {} 行", rows_read, total),
+            None => format!("已读取 {} 行", rows_read),
+        };
+        match total_bytes {
+            Some(total) => detail.push_str(&format!("（{} / {}）", format_bytes(bytes_read), format_bytes(total))),
+            None => detail.push_str(&format!("（{}）", format_bytes(bytes_read))),
+        }
+        if let Some(rate) = rows_per_second {
+            detail.push_str(&format!("，{:.0} 行/秒", rate));
+        }
+        if let Some(eta) = eta_seconds {
+            detail.push_str(&format!("，预计剩余 {:.0} 秒", eta.ceil()));
+        }
+
+        ProcessProgress {
+            step: "2/3".to_string(),
+            message: "正在读取并解析数据...".to_string(),
+            percent: 15 + (fraction * 75.0) as u32,
+            detail,
+            rows_read: Some(rows_read),
+            estimated_total_rows,
+            rows_per_second,
+            eta_seconds,
+            bytes_read: Some(bytes_read),
+            total_bytes,
+        }
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// 基于预聚合立方体进行趋势分析（按日、按周时基于行数据的订单日期）
+pub fn analyze_from_cache(
+    cube: &AggregateCube,
+    store: &ColumnarStore,
+    analysis_type: &str,
+    target: &str,
+    master: Option<&CustomerMaster>,
+    options: &TrendOptions,
+    filter: Option<&RowFilter>,
+) -> Result<MonthlyAnalysisResult, String> {
+    let start_time = std::time::Instant::now();
+    
+    if target.is_empty() {
+        return Err("请选择分析目标".to_string());
+    }
+    options.validate()?;
+
+    // 有客户主数据时，合并同一标准客户下所有编码的数据
+    let codes = match master {
+        Some(master) if analysis_type == "customer" => master.codes_for(target),
+        _ => vec![target],
+    };
+
+    // 有筛选条件时逐行统计，否则按月份可直接使用预聚合立方体
+    let compiled = filter.filter(|f| !f.is_empty()).map(|f| f.compile(store));
+    let series = if options.needs_date() || compiled.is_some() {
+        let rows = codes
+            .iter()
+            .flat_map(|code| store.matching_rows(analysis_type, code))
+            .filter(|&i| compiled.as_ref().is_none_or(|f| f.matches(i)));
+        let (periods, unknown) = collect_rows(store, rows, options);
+        build_series(periods, unknown, store_period_range(store, options), options)
+    } else {
+        let (periods, unknown, range) = collect_from_cube(cube, analysis_type, &codes, options);
+        build_series(periods, unknown, range, options)
+    };
+    let TrendSeries { data: monthly_data, unknown: unknown_month } = series;
+
+    let mut target_name = if analysis_type == "customer" {
+        master
+            .and_then(|master| master.canonical_name(target))
+            .unwrap_or_else(|| cube.customer_name(target))
+            .to_string()
+    } else {
+        String::new()
+    };
+
+    let total_amount: Money = monthly_data.iter().chain(&unknown_month).map(|d| d.total_amount).sum();
+    let total_orders: u32 = monthly_data.iter().chain(&unknown_month).map(|d| d.order_count).sum();
+    let (ytd_amount, last_year_ytd_amount, ytd_growth_rate) = monthly_data
+        .last()
+        .map(|d| (d.ytd_amount, d.last_year_ytd_amount, d.ytd_growth_rate))
+        .unwrap_or_default();
 
     if target_name.is_empty() {
         target_name = target.to_string();
@@ -350,13 +656,284 @@ pub fn analyze_from_cache(
         analysis_type: analysis_type.to_string(),
         target: target.to_string(),
         target_name,
+        options: options.clone(),
         monthly_data,
+        unknown_month,
         total_amount,
         total_orders,
+        ytd_amount,
+        last_year_ytd_amount,
+        ytd_growth_rate,
         process_time_ms,
     })
 }
 
+/// 多个目标的趋势对比，各目标的周期轴一致（整个数据集的时间范围）
+pub fn compare_targets(
+    cube: &AggregateCube,
+    store: &ColumnarStore,
+    analysis_type: &str,
+    targets: &[String],
+    master: Option<&CustomerMaster>,
+    options: &TrendOptions,
+) -> Result<TrendComparisonResult, String> {
+    let start_time = std::time::Instant::now();
+
+    let mut unique_targets: Vec<&str> = Vec::with_capacity(targets.len());
+    for target in targets.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !unique_targets.contains(&target) {
+            unique_targets.push(target);
+        }
+    }
+    if unique_targets.is_empty() {
+        return Err("请选择至少一个分析目标".to_string());
+    }
+
+    let results: Vec<MonthlyAnalysisResult> = unique_targets
+        .par_iter()
+        .map(|target| analyze_from_cache(cube, store, analysis_type, target, master, options, None))
+        .collect::<Result<_, _>>()?;
+
+    let total_amount: Money = results.iter().map(|r| r.total_amount).sum();
+    let total_yuan = total_amount.to_yuan();
+
+    let has_unknown = results.iter().any(|r| r.unknown_month.is_some());
+    let period_count = results[0].monthly_data.len();
+    let period_amount = |result: &MonthlyAnalysisResult, index: usize| -> Money {
+        if index < period_count {
+            result.monthly_data[index].total_amount
+        } else {
+            result.unknown_month.as_ref().map(|d| d.total_amount).unwrap_or_default()
+        }
+    };
+
+    let periods: Vec<ComparisonPeriod> = (0..period_count + usize::from(has_unknown))
+        .map(|index| {
+            let amounts: Vec<Money> = results.iter().map(|r| period_amount(r, index)).collect();
+            let total: Money = amounts.iter().sum();
+            let shares = amounts
+                .iter()
+                .map(|amount| percent_of(amount.to_yuan(), total.to_yuan()))
+                .collect();
+            let period = if index < period_count {
+                results[0].monthly_data[index].month.clone()
+            } else {
+                options.unknown_label().to_string()
+            };
+
+            ComparisonPeriod { period, amounts, shares, total }
+        })
+        .collect();
+
+    let targets = results
+        .into_iter()
+        .map(|result| ComparisonTarget {
+            share: percent_of(result.total_amount.to_yuan(), total_yuan),
+            target: result.target,
+            target_name: result.target_name,
+            total_amount: result.total_amount,
+            total_orders: result.total_orders,
+            data: result.monthly_data,
+        })
+        .collect();
+
+    Ok(TrendComparisonResult {
+        analysis_type: analysis_type.to_string(),
+        options: options.clone(),
+        targets,
+        periods,
+        total_amount,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
+
+/// 各周期汇总、无法归入周期的汇总、数据集的周期范围
+type PeriodSeries = (BTreeMap<Period, CubeCell>, Option<CubeCell>, Option<(Period, Period)>);
+
+/// 由立方体的逐月汇总归并到月、季、年
+fn collect_from_cube(
+    cube: &AggregateCube,
+    analysis_type: &str,
+    codes: &[&str],
+    options: &TrendOptions,
+) -> PeriodSeries {
+    let mut periods: BTreeMap<Period, CubeCell> = BTreeMap::new();
+    let mut unknown: Option<CubeCell> = None;
+
+    for code in codes {
+        let Some(months) = cube.months_of(analysis_type, code) else {
+            continue;
+        };
+        for (month, cell) in months {
+            match parse_year_month(month).and_then(|(year, month)| options.period_of_month(year, month)) {
+                Some(period) => periods.entry(period).or_default().add(cell),
+                None => unknown.get_or_insert_with(CubeCell::default).add(cell),
+            }
+        }
+    }
+
+    let period_of = |month: &str| {
+        parse_year_month(month).and_then(|(year, month)| options.period_of_month(year, month))
+    };
+    let range = cube
+        .month_range()
+        .and_then(|(first, last)| Some((period_of(first)?, period_of(last)?)));
+
+    (periods, unknown, range)
+}
+
+/// 按周期汇总指定的行（按日、按周使用订单日期，其余粒度使用月份列）
+fn collect_rows(
+    store: &ColumnarStore,
+    rows: impl IntoIterator<Item = usize>,
+    options: &TrendOptions,
+) -> (BTreeMap<Period, CubeCell>, Option<CubeCell>) {
+    let mut periods: BTreeMap<Period, CubeCell> = BTreeMap::new();
+    let mut unknown: Option<CubeCell> = None;
+
+    for i in rows {
+        let cell = match options.period_of_row(store, i) {
+            Some(period) => periods.entry(period).or_default(),
+            None => unknown.get_or_insert_with(CubeCell::default),
+        };
+        cell.add_row(store.pay_amount[i], store.recharge_deduction[i], store.total_amount(i));
+    }
+
+    (periods, unknown)
+}
+
+/// 整个存储的周期范围
+fn store_period_range(store: &ColumnarStore, options: &TrendOptions) -> Option<(Period, Period)> {
+    if options.needs_date() {
+        return store
+            .date_range()
+            .map(|(first, last)| (options.period_of_date(first), options.period_of_date(last)));
+    }
+
+    let mut periods = store
+        .months
+        .values()
+        .iter()
+        .filter_map(|month| parse_year_month(month))
+        .filter_map(|(year, month)| options.period_of_month(year, month));
+    let first = periods.next()?;
+    Some(periods.fold((first, first), |(min, max), p| (min.min(p), max.max(p))))
+}
+
+/// 连续周期的趋势数据（含环比、同比）及无法归入周期的汇总
+pub struct TrendSeries {
+    pub data: Vec<MonthlySalesData>,
+    pub unknown: Option<MonthlySalesData>,
+}
+
+/// 指定行的趋势（周期轴为整个存储的时间范围）
+pub fn trend_for_rows(
+    store: &ColumnarStore,
+    rows: impl IntoIterator<Item = usize>,
+    options: &TrendOptions,
+) -> TrendSeries {
+    let (periods, unknown) = collect_rows(store, rows, options);
+    build_series(periods, unknown, store_period_range(store, options), options)
+}
+
+/// 生成连续的周期序列，并计算环比、同比和本年累计
+fn build_series(
+    periods: BTreeMap<Period, CubeCell>,
+    unknown: Option<CubeCell>,
+    range: Option<(Period, Period)>,
+    options: &TrendOptions,
+) -> TrendSeries {
+    let sales_data = |month: String, cell: CubeCell| MonthlySalesData {
+        month,
+        total_amount: cell.total_amount,
+        pay_amount: cell.pay_amount,
+        recharge_deduction: cell.recharge_deduction,
+        order_count: cell.order_count,
+        mom_growth_rate: 0.0,
+        last_year_amount: None,
+        yoy_growth_rate: None,
+        ytd_amount: Money::ZERO,
+        last_year_ytd_amount: None,
+        ytd_growth_rate: None,
+    };
+
+    let axis = range
+        .map(|(first, last)| options.axis(first, last))
+        .unwrap_or_default();
+    let mut data: Vec<MonthlySalesData> = axis
+        .iter()
+        .map(|period| {
+            let cell = periods.get(period).copied().unwrap_or_default();
+            sales_data(options.label(*period), cell)
+        })
+        .collect();
+
+    // 计算环比增长率
+    for i in 1..data.len() {
+        data[i].mom_growth_rate = growth_rate(data[i - 1].total_amount, data[i].total_amount);
+    }
+
+    fill_year_over_year(&mut data, &axis, options);
+
+    TrendSeries {
+        data,
+        unknown: unknown.map(|cell| sales_data(options.unknown_label().to_string(), cell)),
+    }
+}
+
+/// 增长率（%），基期为0且本期大于0时记为100%
+fn growth_rate(previous: Money, current: Money) -> f64 {
+    let previous = previous.to_yuan();
+    let current = current.to_yuan();
+    if previous > 0.0 {
+        ((current - previous) / previous) * 100.0
+    } else if current > 0.0 {
+        100.0
+    } else {
+        0.0
+    }
+}
+
+/// 计算同比及本年（财年）累计
+///
+/// 去年同期早于数据范围时同比为 None。
+fn fill_year_over_year(monthly_data: &mut [MonthlySalesData], axis: &[Period], options: &TrendOptions) {
+    let amounts: BTreeMap<Period, Money> = axis
+        .iter()
+        .zip(monthly_data.iter())
+        .map(|(period, data)| (*period, data.total_amount))
+        .collect();
+    let Some(&first) = axis.first() else {
+        return;
+    };
+
+    let ytd = |period: Period| -> Money {
+        amounts
+            .range(Period { year: period.year, index: 1 }..=period)
+            .map(|(_, amount)| *amount)
+            .sum()
+    };
+
+    for (period, data) in axis.iter().zip(monthly_data.iter_mut()) {
+        data.ytd_amount = ytd(*period);
+
+        let Some(last_year) = options.same_period_last_year(*period).filter(|p| *p >= first) else {
+            continue;
+        };
+
+        let last_year_amount = amounts.get(&last_year).copied().unwrap_or_default();
+        data.last_year_amount = Some(last_year_amount);
+        data.yoy_growth_rate = Some(growth_rate(last_year_amount, data.total_amount));
+
+        // 去年同期累计需要数据覆盖去年的第一个周期
+        if (Period { year: last_year.year, index: 1 }) >= first {
+            let last_year_ytd = ytd(last_year);
+            data.last_year_ytd_amount = Some(last_year_ytd);
+            data.ytd_growth_rate = Some(growth_rate(last_year_ytd, data.ytd_amount));
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ColumnIndices {
     customer_code: usize,
@@ -368,6 +945,8 @@ struct ColumnIndices {
     district: Option<usize>,
     region: Option<usize>,
     date: Option<usize>,
+    // 自定义维度列：(列名, 列号)
+    custom: Vec<(String, usize)>,
 }
 
 fn find_column_indices(header: &[Data]) -> Result<ColumnIndices, String> {
@@ -380,9 +959,14 @@ fn find_column_indices(header: &[Data]) -> Result<ColumnIndices, String> {
     let mut district_idx = None;
     let mut region_idx = None;
     let mut date_idx = None;
+    let mut custom = Vec::new();
 
     for (idx, cell) in header.iter().enumerate() {
         let col_name = data_to_string(cell).trim().to_string();
+        if CUSTOM_DIMENSION_COLUMNS.contains(&col_name.as_str()) {
+            custom.push((col_name, idx));
+            continue;
+        }
         match col_name.as_str() {
             "客户编码" => customer_code_idx = Some(idx),
             "客户名称" | "客户" => customer_name_idx = Some(idx),
@@ -412,13 +996,19 @@ fn find_column_indices(header: &[Data]) -> Result<ColumnIndices, String> {
         district: district_idx,
         region: region_idx,
         date: date_idx,
+        custom,
     })
 }
 
-fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CachedRow> {
+fn parse_row(
+    row: &[Data],
+    indices: &ColumnIndices,
+    code_rules: &CodeNormalizationRules,
+    divisions: &DivisionNormalizer,
+) -> Option<CachedRow> {
     let customer_code = row
         .get(indices.customer_code)
-        .map(|v| data_to_string(v).trim().to_string())?;
+        .map(|v| code_rules.normalize(&data_to_string(v)))?;
     
     if customer_code.is_empty() {
         return None;
@@ -432,33 +1022,24 @@ fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CachedRow> {
 
     let pay_amount = row
         .get(indices.pay_amount)
-        .map(|v| parse_number(v))
-        .unwrap_or(0.0);
+        .map(Money::from_data)
+        .unwrap_or_default();
 
     let recharge_deduction = row
         .get(indices.recharge_deduction)
-        .map(|v| parse_number(v))
-        .unwrap_or(0.0);
+        .map(Money::from_data)
+        .unwrap_or_default();
 
     let total_amount = pay_amount + recharge_deduction;
 
-    let province = indices
-        .province
-        .and_then(|idx| row.get(idx))
-        .map(|v| data_to_string(v).trim().to_string())
-        .filter(|s| !s.is_empty());
-
-    let city = indices
-        .city
-        .and_then(|idx| row.get(idx))
-        .map(|v| data_to_string(v).trim().to_string())
-        .filter(|s| !s.is_empty());
+    let text = |idx: Option<usize>| idx.and_then(|idx| row.get(idx)).map(data_to_string);
 
-    let district = indices
-        .district
-        .and_then(|idx| row.get(idx))
-        .map(|v| data_to_string(v).trim().to_string())
-        .filter(|s| !s.is_empty());
+    // 省市区按行政区词典统一写法（"广东" / "广东省" / "广东 " → "广东省"）
+    let (province, city, district) = divisions.normalize(
+        text(indices.province).as_deref(),
+        text(indices.city).as_deref(),
+        text(indices.district).as_deref(),
+    );
 
     // 组合地区
     let region = if province.is_some() || city.is_some() || district.is_some() {
@@ -481,11 +1062,22 @@ fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CachedRow> {
             .filter(|s| !s.is_empty())
     };
 
-    // 解析月份
-    let month = indices
-        .date
-        .and_then(|idx| row.get(idx))
-        .and_then(|v| extract_month(v));
+    // 解析日期和月份（只有年月时仅保留月份）
+    let date_cell = indices.date.and_then(|idx| row.get(idx));
+    let date = date_cell.and_then(extract_date);
+    let month = match date {
+        Some(date) => Some(date.format("%Y-%m").to_string()),
+        None => date_cell.and_then(extract_month),
+    };
+
+    let custom = indices
+        .custom
+        .iter()
+        .filter_map(|(name, idx)| {
+            let value = data_to_string(row.get(*idx)?).trim().to_string();
+            (!value.is_empty()).then(|| (name.clone(), value))
+        })
+        .collect();
 
     Some(CachedRow {
         customer_code,
@@ -498,9 +1090,56 @@ fn parse_row(row: &[Data], indices: &ColumnIndices) -> Option<CachedRow> {
         district,
         region,
         month,
+        date: date.map(|d| d.format("%Y-%m-%d").to_string()),
+        custom,
     })
 }
 
+/// 提取完整日期（年月日）
+fn extract_date(value: &Data) -> Option<chrono::NaiveDate> {
+    let from_serial = |days: f64| -> Option<chrono::NaiveDate> {
+        if days > 0.0 && days < 100000.0 {
+            let base_date = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?;
+            Some(base_date + chrono::Duration::days(days as i64))
+        } else {
+            None
+        }
+    };
+
+    match value {
+        Data::DateTime(dt) => from_serial(dt.as_f64()),
+        Data::Float(f) => from_serial(*f),
+        Data::DateTimeIso(s) => chrono::NaiveDate::parse_from_str(s.get(0..10)?, "%Y-%m-%d").ok(),
+        Data::String(s) => parse_date_from_string(s),
+        _ => None,
+    }
+}
+
+/// 日期解析用的正则只编译一次，导入时每行都会用到
+fn cached_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("日期正则无效"))
+}
+
+fn parse_date_from_string(s: &str) -> Option<chrono::NaiveDate> {
+    let s = s.trim();
+
+    // 2024-01-15、2024/1/15、2024.01.15、2024年1月15日（可带时间）
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    if let Some(cap) = cached_regex(&DATE, r"^(\d{4})[-/.年](\d{1,2})[-/.月](\d{1,2})").captures(s) {
+        let year = cap.get(1)?.as_str().parse().ok()?;
+        let month = cap.get(2)?.as_str().parse().ok()?;
+        let day = cap.get(3)?.as_str().parse().ok()?;
+        return chrono::NaiveDate::from_ymd_opt(year, month, day);
+    }
+
+    // 20240115 格式
+    if s.len() >= 8 && s.chars().take(8).all(|c| c.is_ascii_digit()) {
+        return chrono::NaiveDate::parse_from_str(&s[0..8], "%Y%m%d").ok();
+    }
+
+    None
+}
+
 /// 提取月份
 fn extract_month(value: &Data) -> Option<String> {
     match value {
@@ -540,30 +1179,24 @@ fn parse_month_from_string(s: &str) -> Option<String> {
     let s = s.trim();
     
     // 2024-01-15, 2024/01/15, 2024.01.15
-    if let Some(cap) = regex_lite::Regex::new(r"^(\d{4})[-/.](\d{1,2})")
-        .ok()
-        .and_then(|re| re.captures(s))
-    {
+    static YEAR_MONTH: OnceLock<Regex> = OnceLock::new();
+    if let Some(cap) = cached_regex(&YEAR_MONTH, r"^(\d{4})[-/.](\d{1,2})").captures(s) {
         let year = cap.get(1)?.as_str();
         let month = cap.get(2)?.as_str();
         return Some(format!("{}-{:0>2}", year, month));
     }
     
     // 2024-01-15 10:30:00 格式（带时间）
-    if let Some(cap) = regex_lite::Regex::new(r"^(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})")
-        .ok()
-        .and_then(|re| re.captures(s))
-    {
+    static YEAR_MONTH_DAY: OnceLock<Regex> = OnceLock::new();
+    if let Some(cap) = cached_regex(&YEAR_MONTH_DAY, r"^(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})").captures(s) {
         let year = cap.get(1)?.as_str();
         let month = cap.get(2)?.as_str();
         return Some(format!("{}-{:0>2}", year, month));
     }
     
     // 2024年1月15日 或 2024年1月 格式
-    if let Some(cap) = regex_lite::Regex::new(r"^(\d{4})年(\d{1,2})月")
-        .ok()
-        .and_then(|re| re.captures(s))
-    {
+    static CHINESE_YEAR_MONTH: OnceLock<Regex> = OnceLock::new();
+    if let Some(cap) = cached_regex(&CHINESE_YEAR_MONTH, r"^(\d{4})年(\d{1,2})月").captures(s) {
         let year = cap.get(1)?.as_str();
         let month = cap.get(2)?.as_str();
         return Some(format!("{}-{:0>2}", year, month));
@@ -604,11 +1237,3 @@ fn data_to_string(value: &Data) -> String {
     }
 }
 
-fn parse_number(value: &Data) -> f64 {
-    match value {
-        Data::Float(f) => *f,
-        Data::Int(i) => *i as f64,
-        Data::String(s) => s.trim().parse().unwrap_or(0.0),
-        _ => 0.0,
-    }
-}
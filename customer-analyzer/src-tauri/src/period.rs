@@ -0,0 +1,244 @@
+use crate::columnar_store::ColumnarStore;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// 趋势分析的时间粒度
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Day,
+    // ISO 周（周一开始，跨年的周归属 ISO 年份）
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    Year,
+}
+
+/// 趋势分析参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TrendOptions {
+    pub granularity: Granularity,
+    // 财年起始月份（1 ~ 12，默认1月即自然年）；影响季度、年度划分和本年累计
+    pub fiscal_year_start_month: u32,
+}
+
+impl Default for TrendOptions {
+    fn default() -> Self {
+        TrendOptions {
+            granularity: Granularity::Month,
+            fiscal_year_start_month: 1,
+        }
+    }
+}
+
+/// 一个统计周期：所属年份（财年或 ISO 年）及年内序号（从1开始）
+///
+/// 财年以起始月份所在的自然年命名，例如起始月为4月时 2024-04 ~ 2025-03 为 FY2024。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Period {
+    pub year: i32,
+    pub index: u32,
+}
+
+impl TrendOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=12).contains(&self.fiscal_year_start_month) {
+            return Err("财年起始月份必须在1到12之间".to_string());
+        }
+        Ok(())
+    }
+
+    /// 按日、按周需要订单日期；按月、季、年只需要月份
+    pub fn needs_date(&self) -> bool {
+        matches!(self.granularity, Granularity::Day | Granularity::Week)
+    }
+
+    /// 没有日期（或月份）的行归入的周期名称
+    pub fn unknown_label(&self) -> &'static str {
+        if self.needs_date() {
+            "未知日期"
+        } else {
+            crate::aggregate_cube::UNKNOWN_MONTH
+        }
+    }
+
+    /// 自然年月 → (财年, 财年内月份)
+    fn fiscal_month(&self, year: i32, month: u32) -> (i32, u32) {
+        let start = self.fiscal_year_start_month;
+        if month >= start {
+            (year, month - start + 1)
+        } else {
+            (year - 1, month + 12 - start + 1)
+        }
+    }
+
+    /// 财年第一天
+    fn fiscal_year_start(&self, year: i32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, self.fiscal_year_start_month, 1)
+    }
+
+    /// 月份所在的周期（按日、按周时为 None）
+    pub fn period_of_month(&self, year: i32, month: u32) -> Option<Period> {
+        let (fiscal_year, fiscal_month) = self.fiscal_month(year, month);
+        match self.granularity {
+            Granularity::Month => Some(Period { year: fiscal_year, index: fiscal_month }),
+            Granularity::Quarter => Some(Period { year: fiscal_year, index: (fiscal_month - 1) / 3 + 1 }),
+            Granularity::Year => Some(Period { year: fiscal_year, index: 1 }),
+            Granularity::Day | Granularity::Week => None,
+        }
+    }
+
+    /// 日期所在的周期
+    pub fn period_of_date(&self, date: NaiveDate) -> Period {
+        match self.granularity {
+            Granularity::Day => {
+                let (fiscal_year, _) = self.fiscal_month(date.year(), date.month());
+                let start = self.fiscal_year_start(fiscal_year).unwrap_or(date);
+                Period {
+                    year: fiscal_year,
+                    index: (date - start).num_days() as u32 + 1,
+                }
+            },
+            Granularity::Week => {
+                let week = date.iso_week();
+                Period { year: week.year(), index: week.week() }
+            },
+            _ => self
+                .period_of_month(date.year(), date.month())
+                .unwrap_or(Period { year: date.year(), index: 1 }),
+        }
+    }
+
+    /// 第 i 行所在的周期（按日、按周使用订单日期，其余粒度使用月份列）
+    pub fn period_of_row(&self, store: &ColumnarStore, i: usize) -> Option<Period> {
+        if self.needs_date() {
+            store.date(i).map(|date| self.period_of_date(date))
+        } else {
+            store
+                .month(i)
+                .and_then(parse_year_month)
+                .and_then(|(year, month)| self.period_of_month(year, month))
+        }
+    }
+
+    /// 一年内的周期数
+    fn periods_in_year(&self, year: i32) -> u32 {
+        match self.granularity {
+            Granularity::Day => match (self.fiscal_year_start(year), self.fiscal_year_start(year + 1)) {
+                (Some(start), Some(end)) => (end - start).num_days() as u32,
+                _ => 365,
+            },
+            Granularity::Week => {
+                if NaiveDate::from_isoywd_opt(year, 53, Weekday::Mon).is_some() { 53 } else { 52 }
+            },
+            Granularity::Month => 12,
+            Granularity::Quarter => 4,
+            Granularity::Year => 1,
+        }
+    }
+
+    /// 下一个周期
+    pub fn next(&self, period: Period) -> Period {
+        if period.index >= self.periods_in_year(period.year) {
+            Period { year: period.year + 1, index: 1 }
+        } else {
+            Period { year: period.year, index: period.index + 1 }
+        }
+    }
+
+    /// 周期第一天（仅按日时使用）
+    fn day_of(&self, period: Period) -> Option<NaiveDate> {
+        let start = self.fiscal_year_start(period.year)?;
+        start.checked_add_signed(chrono::Duration::days(period.index as i64 - 1))
+    }
+
+    /// 去年同期（去年没有对应周期时为 None，例如第53周、闰年2月29日）
+    pub fn same_period_last_year(&self, period: Period) -> Option<Period> {
+        // 按日时用日历日期对应（闰年2月29日之后的年内序号与平年相差一天）
+        if self.granularity == Granularity::Day {
+            let date = self.day_of(period)?;
+            return date.with_year(date.year() - 1).map(|date| self.period_of_date(date));
+        }
+
+        let last_year = Period { year: period.year - 1, index: period.index };
+        (period.index <= self.periods_in_year(last_year.year)).then_some(last_year)
+    }
+
+    /// 从 first 到 last（含）的连续周期
+    pub fn axis(&self, first: Period, last: Period) -> Vec<Period> {
+        let mut axis = Vec::new();
+        let mut current = first;
+        while current <= last {
+            axis.push(current);
+            current = self.next(current);
+        }
+        axis
+    }
+
+    /// 周期名称：按日 "2024-01-15"，按周 "2024-W03"，按月 "2024-01"，
+    /// 按季 "2024-Q1"，按年 "2024"（非自然年时季度和年度以 "FY" 开头）
+    pub fn label(&self, period: Period) -> String {
+        let fiscal_prefix = if self.fiscal_year_start_month == 1 { "" } else { "FY" };
+        match self.granularity {
+            Granularity::Day => self
+                .day_of(period)
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            Granularity::Week => format!("{}-W{:02}", period.year, period.index),
+            Granularity::Month => {
+                let offset = self.fiscal_year_start_month - 1 + period.index - 1;
+                format!("{}-{:02}", period.year + (offset / 12) as i32, offset % 12 + 1)
+            },
+            Granularity::Quarter => format!("{}{}-Q{}", fiscal_prefix, period.year, period.index),
+            Granularity::Year => format!("{}{}", fiscal_prefix, period.year),
+        }
+    }
+}
+
+/// 解析 "YYYY-MM" 格式的月份
+pub fn parse_year_month(month: &str) -> Option<(i32, u32)> {
+    let (year, month) = month.split_once('-')?;
+    let year: i32 = year.parse().ok()?;
+    let month: u32 = month.parse().ok()?;
+    (1..=12).contains(&month).then_some((year, month))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(fiscal_year_start_month: u32) -> TrendOptions {
+        TrendOptions {
+            granularity: Granularity::Day,
+            fiscal_year_start_month,
+        }
+    }
+
+    fn last_year_of(options: &TrendOptions, date: &str) -> Option<String> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        options
+            .same_period_last_year(options.period_of_date(date))
+            .map(|period| options.label(period))
+    }
+
+    #[test]
+    fn daily_last_year_around_leap_day() {
+        let options = daily(1);
+        assert_eq!(last_year_of(&options, "2024-02-28").as_deref(), Some("2023-02-28"));
+        assert_eq!(last_year_of(&options, "2024-02-29"), None);
+        assert_eq!(last_year_of(&options, "2024-03-01").as_deref(), Some("2023-03-01"));
+        assert_eq!(last_year_of(&options, "2024-12-31").as_deref(), Some("2023-12-31"));
+        assert_eq!(last_year_of(&options, "2025-03-01").as_deref(), Some("2024-03-01"));
+    }
+
+    #[test]
+    fn daily_last_year_with_fiscal_year() {
+        // 财年从4月开始：2024-03-01 属于 FY2023，去年同期仍是日历上的 2023-03-01
+        let options = daily(4);
+        assert_eq!(last_year_of(&options, "2024-03-01").as_deref(), Some("2023-03-01"));
+        assert_eq!(last_year_of(&options, "2024-02-29"), None);
+        assert_eq!(last_year_of(&options, "2024-04-01").as_deref(), Some("2023-04-01"));
+    }
+}
@@ -0,0 +1,470 @@
+use crate::aggregate_cube::CubeCell;
+use crate::columnar_store::{Dictionary, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::data_registry::DataCache;
+use crate::excel_processor::RankMetric;
+use crate::monthly_analysis::CUSTOM_DIMENSION_PREFIX;
+use crate::period::{Period, TrendOptions};
+use crate::row_filter::RowFilter;
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 周期轴（按 TrendOptions 的粒度划分）
+pub const PERIOD_AXIS: &str = "period";
+
+/// 维度值为空时的名称
+pub const EMPTY_LABEL: &str = "（空）";
+
+/// 透视表的度量
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PivotMeasure {
+    #[default]
+    TotalAmount,
+    PayAmount,
+    RechargeDeduction,
+    OrderCount,
+    // 不重复客户数（合计按去重后的客户数计算，不是各单元格相加）
+    DistinctCustomers,
+}
+
+impl PivotMeasure {
+    fn rank_metric(self) -> Option<RankMetric> {
+        match self {
+            PivotMeasure::TotalAmount => Some(RankMetric::TotalAmount),
+            PivotMeasure::PayAmount => Some(RankMetric::PayAmount),
+            PivotMeasure::RechargeDeduction => Some(RankMetric::RechargeDeduction),
+            PivotMeasure::OrderCount => Some(RankMetric::OrderCount),
+            PivotMeasure::DistinctCustomers => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PivotMeasure::TotalAmount => "总金额",
+            PivotMeasure::PayAmount => "支付金额",
+            PivotMeasure::RechargeDeduction => "充值抵扣",
+            PivotMeasure::OrderCount => "订单数",
+            PivotMeasure::DistinctCustomers => "客户数",
+        }
+    }
+}
+
+/// 透视表参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PivotOptions {
+    // 行、列维度：customer / province / city / district / region / custom:列名 / period
+    pub row_dimension: String,
+    pub column_dimension: String,
+    pub measure: PivotMeasure,
+    // 周期轴的粒度和财年起始月份
+    pub trend: TrendOptions,
+    // 行的分组维度（例如行为城市、按省份小计），为空时不计算小计
+    pub subtotal_dimension: Option<String>,
+}
+
+impl Default for PivotOptions {
+    fn default() -> Self {
+        PivotOptions {
+            row_dimension: "province".to_string(),
+            column_dimension: PERIOD_AXIS.to_string(),
+            measure: PivotMeasure::TotalAmount,
+            trend: TrendOptions::default(),
+            subtotal_dimension: None,
+        }
+    }
+}
+
+/// 透视表中的一行
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PivotRow {
+    // 分组维度的值（设置了小计维度时）
+    pub group: Option<String>,
+    pub label: String,
+    // 行维度为客户时的客户名称
+    pub name: Option<String>,
+    pub is_subtotal: bool,
+    // 与 columns 对齐
+    pub values: Vec<f64>,
+    pub total: f64,
+}
+
+/// 透视表结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PivotResult {
+    pub options: PivotOptions,
+    pub columns: Vec<String>,
+    pub rows: Vec<PivotRow>,
+    pub column_totals: Vec<f64>,
+    pub grand_total: f64,
+    // 参与汇总的行数（筛选后）
+    pub matched_rows: usize,
+    pub process_time_ms: u128,
+}
+
+/// 维度的显示名称
+pub fn dimension_label(dimension: &str) -> String {
+    if let Some(name) = dimension.strip_prefix(CUSTOM_DIMENSION_PREFIX) {
+        return name.to_string();
+    }
+    match dimension {
+        "customer" => "客户",
+        "province" => "省份",
+        "city" => "城市",
+        "district" => "区县",
+        "region" => "地区",
+        PERIOD_AXIS => "周期",
+        other => other,
+    }
+    .to_string()
+}
+
+#[derive(Default)]
+struct PivotCell {
+    cell: CubeCell,
+    customers: HashSet<u32>,
+}
+
+impl PivotCell {
+    fn value(&self, measure: PivotMeasure) -> f64 {
+        match measure.rank_metric() {
+            Some(metric) => self.cell.metric_value(metric),
+            None => self.customers.len() as f64,
+        }
+    }
+}
+
+/// 一个轴：将每一行映射为标签序号（相同标签合并，例如客户主数据中的别名编码）
+struct Axis<'a> {
+    source: AxisSource<'a>,
+    labels: Vec<String>,
+    by_label: HashMap<String, u32>,
+    // 周期轴：周期 → 标签序号，以及出现过的最早、最晚周期
+    periods: HashMap<Option<Period>, u32>,
+    period_range: Option<(Period, Period)>,
+}
+
+enum AxisSource<'a> {
+    // 维度ID → 标签序号（NONE_ID 单独处理）
+    Dimension { column: &'a [u32], id_map: Vec<u32> },
+    Period(&'a TrendOptions),
+}
+
+impl<'a> Axis<'a> {
+    fn new(
+        data: &'a DataCache,
+        dimension: &str,
+        trend: &'a TrendOptions,
+        master: Option<&CustomerMaster>,
+    ) -> Result<Self, String> {
+        let mut axis = Axis {
+            source: AxisSource::Period(trend),
+            labels: Vec::new(),
+            by_label: HashMap::new(),
+            periods: HashMap::new(),
+            period_range: None,
+        };
+
+        if dimension != PERIOD_AXIS {
+            let (column, dict): (&[u32], &Dictionary) = data
+                .store
+                .dimension(dimension)
+                .ok_or_else(|| format!("不支持的维度: {}", dimension))?;
+            let id_map = dict
+                .values()
+                .iter()
+                .map(|value| match master {
+                    Some(master) if dimension == "customer" => axis.intern(master.canonical_code(value)),
+                    _ => axis.intern(value),
+                })
+                .collect();
+            axis.source = AxisSource::Dimension { column, id_map };
+        }
+
+        Ok(axis)
+    }
+
+    fn intern(&mut self, label: &str) -> u32 {
+        if let Some(&index) = self.by_label.get(label) {
+            return index;
+        }
+        let index = self.labels.len() as u32;
+        self.labels.push(label.to_string());
+        self.by_label.insert(label.to_string(), index);
+        index
+    }
+
+    /// 第 i 行的标签序号
+    fn key(&mut self, data: &DataCache, i: usize) -> u32 {
+        let trend = match &self.source {
+            AxisSource::Dimension { column, id_map } => {
+                let id = column[i];
+                return if id == NONE_ID { self.intern(EMPTY_LABEL) } else { id_map[id as usize] };
+            },
+            AxisSource::Period(trend) => *trend,
+        };
+
+        let period = trend.period_of_row(&data.store, i);
+
+        if let Some(&index) = self.periods.get(&period) {
+            return index;
+        }
+        let index = match period {
+            Some(period) => {
+                self.period_range = Some(match self.period_range {
+                    Some((first, last)) => (first.min(period), last.max(period)),
+                    None => (period, period),
+                });
+                self.intern(&trend.label(period))
+            },
+            None => self.intern(trend.unknown_label()),
+        };
+        self.periods.insert(period, index);
+        index
+    }
+
+    /// 标签的排列顺序：周期轴按时间（补齐中间没有数据的周期，未知周期在最后），
+    /// 其他维度按合计从大到小
+    fn order(&mut self, totals: &HashMap<u32, PivotCell>, measure: PivotMeasure) -> Vec<u32> {
+        if let (AxisSource::Period(trend), Some((first, last))) = (&self.source, self.period_range) {
+            let trend = *trend;
+            let mut order: Vec<u32> = trend
+                .axis(first, last)
+                .into_iter()
+                .map(|period| self.intern(&trend.label(period)))
+                .collect();
+            if let Some(&unknown) = self.periods.get(&None) {
+                order.push(unknown);
+            }
+            return order;
+        }
+
+        let mut order: Vec<u32> = totals.keys().copied().collect();
+        let value = |index: &u32| totals.get(index).map(|cell| cell.value(measure)).unwrap_or_default();
+        order.sort_by(|a, b| {
+            value(b)
+                .partial_cmp(&value(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| self.labels[*a as usize].cmp(&self.labels[*b as usize]))
+        });
+        order
+    }
+}
+
+/// 生成透视表：行维度 × 列维度（或周期）
+pub fn build(
+    data: &DataCache,
+    options: &PivotOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<PivotResult, String> {
+    let start_time = std::time::Instant::now();
+    options.trend.validate()?;
+
+    let store = &data.store;
+    let measure = options.measure;
+    let track_customers = measure == PivotMeasure::DistinctCustomers;
+
+    let mut rows = Axis::new(data, &options.row_dimension, &options.trend, master)?;
+    let mut columns = Axis::new(data, &options.column_dimension, &options.trend, master)?;
+    let mut groups = match &options.subtotal_dimension {
+        Some(dimension) => Some(Axis::new(data, dimension, &options.trend, master)?),
+        None => None,
+    };
+
+    // 有客户主数据时按标准客户去重
+    let customer_ids: Option<Vec<u32>> = master
+        .filter(|_| track_customers)
+        .map(|master| master.canonical_ids(store.customer_codes.values()));
+
+    let compiled = filter.filter(|f| !f.is_empty()).map(|f| f.compile(store));
+
+    let mut cells: HashMap<(u32, u32, u32), PivotCell> = HashMap::new();
+    let mut row_totals: HashMap<(u32, u32), PivotCell> = HashMap::new();
+    let mut column_totals: HashMap<u32, PivotCell> = HashMap::new();
+    let mut group_cells: HashMap<(u32, u32), PivotCell> = HashMap::new();
+    let mut group_totals: HashMap<u32, PivotCell> = HashMap::new();
+    let mut grand_total = PivotCell::default();
+    let mut matched_rows = 0;
+
+    for i in 0..store.len() {
+        if compiled.as_ref().is_some_and(|f| !f.matches(i)) {
+            continue;
+        }
+        matched_rows += 1;
+
+        let row = rows.key(data, i);
+        let column = columns.key(data, i);
+        let group = groups.as_mut().map_or(0, |groups| groups.key(data, i));
+        let customer = match &customer_ids {
+            Some(ids) => ids[store.customer[i] as usize],
+            None => store.customer[i],
+        };
+
+        let add = |target: &mut PivotCell| {
+            target.cell.add_row(store.pay_amount[i], store.recharge_deduction[i], store.total_amount(i));
+            if track_customers {
+                target.customers.insert(customer);
+            }
+        };
+        add(cells.entry((group, row, column)).or_default());
+        add(row_totals.entry((group, row)).or_default());
+        add(column_totals.entry(column).or_default());
+        if groups.is_some() {
+            add(group_cells.entry((group, column)).or_default());
+            add(group_totals.entry(group).or_default());
+        }
+        add(&mut grand_total);
+    }
+
+    let column_order = columns.order(&column_totals, measure);
+    let value_of = |cell: Option<&PivotCell>| cell.map(|c| c.value(measure)).unwrap_or_default();
+
+    // 行维度的合计（跨分组），用于行排序
+    let mut row_sums: HashMap<u32, PivotCell> = HashMap::new();
+    for (&(_, row), cell) in &row_totals {
+        let sum = row_sums.entry(row).or_default();
+        sum.cell.add(&cell.cell);
+        sum.customers.extend(&cell.customers);
+    }
+    let row_order = rows.order(&row_sums, measure);
+    let group_order = match groups.as_mut() {
+        Some(groups) => groups.order(&group_totals, measure),
+        None => vec![0],
+    };
+
+    let customer_name = |label: &str| -> Option<String> {
+        if options.row_dimension != "customer" {
+            return None;
+        }
+        let name = master
+            .and_then(|master| master.canonical_name(label))
+            .unwrap_or_else(|| data.cube.customer_name(label));
+        Some(name.to_string())
+    };
+
+    let mut result_rows = Vec::new();
+    for &group in &group_order {
+        let group_label = groups.as_ref().map(|groups| groups.labels[group as usize].clone());
+        for &row in &row_order {
+            let Some(total) = row_totals.get(&(group, row)) else {
+                continue;
+            };
+            let label = &rows.labels[row as usize];
+            result_rows.push(PivotRow {
+                group: group_label.clone(),
+                label: label.clone(),
+                name: customer_name(label),
+                is_subtotal: false,
+                values: column_order
+                    .iter()
+                    .map(|&column| value_of(cells.get(&(group, row, column))))
+                    .collect(),
+                total: total.value(measure),
+            });
+        }
+
+        if groups.is_some() {
+            result_rows.push(PivotRow {
+                group: group_label,
+                label: "小计".to_string(),
+                name: None,
+                is_subtotal: true,
+                values: column_order
+                    .iter()
+                    .map(|&column| value_of(group_cells.get(&(group, column))))
+                    .collect(),
+                total: value_of(group_totals.get(&group)),
+            });
+        }
+    }
+
+    Ok(PivotResult {
+        options: options.clone(),
+        columns: column_order
+            .iter()
+            .map(|&column| columns.labels[column as usize].clone())
+            .collect(),
+        rows: result_rows,
+        column_totals: column_order
+            .iter()
+            .map(|&column| value_of(column_totals.get(&column)))
+            .collect(),
+        grand_total: grand_total.value(measure),
+        matched_rows,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
+
+/// 将透视表导出为Excel文件
+pub fn export_xlsx(result: &PivotResult, file_path: &str) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+
+    let plain = Format::new();
+    let bold = Format::new().set_bold();
+    let number = match result.options.measure {
+        PivotMeasure::OrderCount | PivotMeasure::DistinctCustomers => Format::new().set_num_format("#,##0"),
+        _ => Format::new().set_num_format("#,##0.00"),
+    };
+    let bold_number = number.clone().set_bold();
+
+    // 表头：[分组维度] 行维度 [客户名称] 各列 合计
+    let has_groups = result.options.subtotal_dimension.is_some();
+    let has_names = result.options.row_dimension == "customer";
+    let mut headers: Vec<String> = Vec::new();
+    if let Some(dimension) = &result.options.subtotal_dimension {
+        headers.push(dimension_label(dimension));
+    }
+    headers.push(dimension_label(&result.options.row_dimension));
+    if has_names {
+        headers.push("客户名称".to_string());
+    }
+    let value_col = headers.len() as u16;
+    headers.extend(result.columns.iter().cloned());
+    headers.push(format!("合计（{}）", result.options.measure.label()));
+
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, header, &bold)
+            .map_err(|e| format!("写入表头失败: {}", e))?;
+    }
+
+    // 数据行，最后一行为总计
+    let total_row = PivotRow {
+        group: None,
+        label: "合计".to_string(),
+        name: None,
+        is_subtotal: true,
+        values: result.column_totals.clone(),
+        total: result.grand_total,
+    };
+
+    for (index, row) in result.rows.iter().chain(std::iter::once(&total_row)).enumerate() {
+        let row_idx = index as u32 + 1;
+        let (text_format, number_format) = if row.is_subtotal { (&bold, &bold_number) } else { (&plain, &number) };
+
+        let mut texts: Vec<&str> = Vec::with_capacity(3);
+        if has_groups {
+            texts.push(row.group.as_deref().unwrap_or_default());
+        }
+        texts.push(&row.label);
+        if has_names {
+            texts.push(row.name.as_deref().unwrap_or_default());
+        }
+        for (col, text) in texts.into_iter().enumerate() {
+            worksheet.write_string_with_format(row_idx, col as u16, text, text_format)
+                .map_err(|e| format!("写入数据失败: {}", e))?;
+        }
+
+        for (offset, value) in row.values.iter().chain(std::iter::once(&row.total)).enumerate() {
+            worksheet.write_number_with_format(row_idx, value_col + offset as u16, *value, number_format)
+                .map_err(|e| format!("写入数据失败: {}", e))?;
+        }
+    }
+
+    workbook.save(file_path)
+        .map_err(|e| format!("保存Excel文件失败: {}", e))?;
+    Ok(())
+}
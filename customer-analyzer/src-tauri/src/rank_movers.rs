@@ -0,0 +1,216 @@
+use crate::customer_master::CustomerMaster;
+use crate::data_registry::DataCache;
+use crate::excel_processor::{self, RankDirection, RankMetric, RankedCustomer, TopNOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// 对比期间：数据源 + 可选的月份区间（格式 "2024-01"，含首尾）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodSpec {
+    pub data_source_ids: Vec<String>,
+    #[serde(default)]
+    pub start_month: Option<String>,
+    #[serde(default)]
+    pub end_month: Option<String>,
+}
+
+/// 排名变化参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RankMoversOptions {
+    pub metric: RankMetric,
+    // 只关注各期前N名（决定榜单成员及新上榜 / 跌出榜单），未设置时为全部客户
+    pub top_n: Option<usize>,
+    // 上升 / 下降榜单的条数
+    pub movers_limit: usize,
+}
+
+impl Default for RankMoversOptions {
+    fn default() -> Self {
+        RankMoversOptions {
+            metric: RankMetric::TotalAmount,
+            top_n: None,
+            movers_limit: 20,
+        }
+    }
+}
+
+/// 单个客户在两个期间的排名与指标
+///
+/// 排名和指标取自该期间全部客户的排名，不受前N名限制；该期间没有数据时排名为空。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankMovement {
+    pub customer_code: String,
+    pub customer_name: String,
+    pub base_rank: Option<usize>,
+    pub compare_rank: Option<usize>,
+    // 是否在该期间的前N名内
+    pub in_base_top: bool,
+    pub in_compare_top: bool,
+    pub base_value: f64,
+    pub compare_value: f64,
+    // 名次变化（正数为上升）
+    pub rank_change: Option<i64>,
+    pub value_change: f64,
+    // 指标变化率（%），基期为0时为空
+    pub value_change_rate: Option<f64>,
+}
+
+/// 排名变化报告
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankMoversResult {
+    pub options: RankMoversOptions,
+    pub base: PeriodSpec,
+    pub compare: PeriodSpec,
+    pub base_total: f64,
+    pub compare_total: f64,
+    // 任一期间进入前N名的客户，按对比期排名排序，对比期没有数据的排在最后
+    pub customers: Vec<RankMovement>,
+    // 新上榜：对比期上榜、基期未上榜
+    pub entered: Vec<RankMovement>,
+    // 跌出榜单：基期上榜、对比期未上榜
+    pub dropped: Vec<RankMovement>,
+    pub risers: Vec<RankMovement>,
+    pub fallers: Vec<RankMovement>,
+    pub process_time_ms: u128,
+}
+
+/// 某个期间全部客户的排名
+fn rank_period(
+    data: &DataCache,
+    spec: &PeriodSpec,
+    options: &RankMoversOptions,
+    master: Option<&CustomerMaster>,
+) -> (Vec<RankedCustomer>, f64) {
+    let mut customers = data
+        .cube
+        .customer_totals_between(spec.start_month.as_deref(), spec.end_month.as_deref());
+    if let Some(master) = master {
+        customers = master.merge_customers(customers);
+    }
+
+    let ranking = excel_processor::rank_customers(
+        customers,
+        &TopNOptions {
+            n: usize::MAX,
+            metric: options.metric,
+            direction: RankDirection::Desc,
+            min_amount: None,
+        },
+        0,
+    );
+
+    (ranking.top_customers, ranking.metric_total)
+}
+
+/// 比较两个期间的客户排名（有客户主数据时按标准客户合并编码）
+pub fn compare(
+    base_data: &DataCache,
+    base: &PeriodSpec,
+    compare_data: &DataCache,
+    compare: &PeriodSpec,
+    options: &RankMoversOptions,
+    master: Option<&CustomerMaster>,
+) -> RankMoversResult {
+    let start_time = std::time::Instant::now();
+
+    let (base_ranking, base_total) = rank_period(base_data, base, options, master);
+    let (compare_ranking, compare_total) = rank_period(compare_data, compare, options, master);
+
+    let base_map: HashMap<&str, &RankedCustomer> = base_ranking
+        .iter()
+        .map(|c| (c.customer.customer_code.as_str(), c))
+        .collect();
+    let compare_map: HashMap<&str, &RankedCustomer> = compare_ranking
+        .iter()
+        .map(|c| (c.customer.customer_code.as_str(), c))
+        .collect();
+
+    // 榜单成员只看前N名，排名和指标使用完整排名
+    let top_n = options.top_n.unwrap_or(usize::MAX);
+    let codes: BTreeSet<&str> = base_ranking
+        .iter()
+        .chain(compare_ranking.iter())
+        .filter(|c| c.rank <= top_n)
+        .map(|c| c.customer.customer_code.as_str())
+        .collect();
+
+    let mut customers: Vec<RankMovement> = codes
+        .into_iter()
+        .map(|code| {
+            let base_entry = base_map.get(code);
+            let compare_entry = compare_map.get(code);
+            let base_value = base_entry.map(|c| c.metric_value).unwrap_or(0.0);
+            let compare_value = compare_entry.map(|c| c.metric_value).unwrap_or(0.0);
+            let customer_name = compare_entry
+                .or(base_entry)
+                .map(|c| c.customer.customer_name.clone())
+                .unwrap_or_default();
+            let base_rank = base_entry.map(|c| c.rank);
+            let compare_rank = compare_entry.map(|c| c.rank);
+
+            RankMovement {
+                customer_code: code.to_string(),
+                customer_name,
+                base_rank,
+                compare_rank,
+                in_base_top: base_rank.is_some_and(|rank| rank <= top_n),
+                in_compare_top: compare_rank.is_some_and(|rank| rank <= top_n),
+                base_value,
+                compare_value,
+                rank_change: match (base_rank, compare_rank) {
+                    (Some(b), Some(c)) => Some(b as i64 - c as i64),
+                    _ => None,
+                },
+                value_change: compare_value - base_value,
+                value_change_rate: (base_value != 0.0)
+                    .then(|| (compare_value - base_value) / base_value.abs() * 100.0),
+            }
+        })
+        .collect();
+
+    customers.sort_by_key(|c| (c.compare_rank.unwrap_or(usize::MAX), c.base_rank.unwrap_or(usize::MAX)));
+
+    let entered: Vec<RankMovement> = customers
+        .iter()
+        .filter(|c| !c.in_base_top)
+        .cloned()
+        .collect();
+
+    let mut dropped: Vec<RankMovement> = customers
+        .iter()
+        .filter(|c| !c.in_compare_top)
+        .cloned()
+        .collect();
+    dropped.sort_by_key(|c| c.base_rank);
+
+    let mut risers: Vec<RankMovement> = customers
+        .iter()
+        .filter(|c| c.rank_change.is_some_and(|change| change > 0))
+        .cloned()
+        .collect();
+    risers.sort_by_key(|c| std::cmp::Reverse(c.rank_change));
+    risers.truncate(options.movers_limit);
+
+    let mut fallers: Vec<RankMovement> = customers
+        .iter()
+        .filter(|c| c.rank_change.is_some_and(|change| change < 0))
+        .cloned()
+        .collect();
+    fallers.sort_by_key(|c| c.rank_change);
+    fallers.truncate(options.movers_limit);
+
+    RankMoversResult {
+        options: options.clone(),
+        base: base.clone(),
+        compare: compare.clone(),
+        base_total,
+        compare_total,
+        customers,
+        entered,
+        dropped,
+        risers,
+        fallers,
+        process_time_ms: start_time.elapsed().as_millis(),
+    }
+}
@@ -0,0 +1,446 @@
+use crate::columnar_store::{ColumnarStore, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::excel_processor::percent_of;
+use crate::money::Money;
+use crate::period::parse_year_month;
+use crate::row_filter::RowFilter;
+use chrono::NaiveDate;
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// 客户分群（R、F、M 均与平均得分比较高低）：(R高, F高, M高, 名称)
+const SEGMENTS: [(bool, bool, bool, &str); 8] = [
+    (true, true, true, "重要价值"),
+    (true, false, true, "重要发展"),
+    (false, true, true, "重要保持"),
+    (false, false, true, "重要挽留"),
+    (true, true, false, "一般价值"),
+    (true, false, false, "一般发展"),
+    (false, true, false, "一般保持"),
+    (false, false, false, "一般挽留"),
+];
+
+/// 评分方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RfmBinning {
+    // 按客户排名等分（bins 档）
+    #[default]
+    Quantile,
+    // 按阈值分档（n 个阈值得到 n + 1 档）
+    Threshold,
+}
+
+/// RFM 分析参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RfmOptions {
+    // 参考日期（"YYYY-MM-DD"），默认为数据中的最后一天；之后的订单不计入
+    pub reference_date: Option<String>,
+    pub binning: RfmBinning,
+    // 等分档数（2 ~ 10）
+    pub bins: u32,
+    // 阈值（从小到大）：最近购买距今天数、购买次数、购买金额（元）
+    pub recency_thresholds: Vec<f64>,
+    pub frequency_thresholds: Vec<f64>,
+    pub monetary_thresholds: Vec<f64>,
+}
+
+impl Default for RfmOptions {
+    fn default() -> Self {
+        RfmOptions {
+            reference_date: None,
+            binning: RfmBinning::Quantile,
+            bins: 5,
+            recency_thresholds: Vec::new(),
+            frequency_thresholds: Vec::new(),
+            monetary_thresholds: Vec::new(),
+        }
+    }
+}
+
+/// 筛选条件中的 RFM 客户分群
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RfmSegmentFilter {
+    pub options: RfmOptions,
+    pub segments: Vec<String>,
+}
+
+/// 一个客户的 RFM 得分
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RfmCustomer {
+    pub customer_code: String,
+    pub customer_name: String,
+    pub last_purchase: String,
+    pub recency_days: i64,
+    pub frequency: u32,
+    pub monetary: Money,
+    pub r_score: u32,
+    pub f_score: u32,
+    pub m_score: u32,
+    // 例如 "545"
+    pub rfm_score: String,
+    pub segment: String,
+}
+
+/// 一个客户分群的汇总
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RfmSegmentSummary {
+    pub segment: String,
+    pub customer_count: usize,
+    pub customer_share: f64,
+    pub total_amount: Money,
+    pub amount_share: f64,
+    pub average_recency_days: f64,
+    pub average_frequency: f64,
+}
+
+/// RFM 分析结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RfmResult {
+    pub options: RfmOptions,
+    pub reference_date: String,
+    // 按购买金额从高到低
+    pub customers: Vec<RfmCustomer>,
+    pub segments: Vec<RfmSegmentSummary>,
+    pub average_r_score: f64,
+    pub average_f_score: f64,
+    pub average_m_score: f64,
+    // 没有日期和月份、或晚于参考日期的行
+    pub excluded_rows: usize,
+    pub process_time_ms: u128,
+}
+
+impl RfmOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.binning {
+            RfmBinning::Quantile => {
+                if !(2..=10).contains(&self.bins) {
+                    return Err("分档数必须在2到10之间".to_string());
+                }
+            },
+            RfmBinning::Threshold => {
+                for (name, thresholds) in [
+                    ("最近购买天数", &self.recency_thresholds),
+                    ("购买次数", &self.frequency_thresholds),
+                    ("购买金额", &self.monetary_thresholds),
+                ] {
+                    if thresholds.is_empty() {
+                        return Err(format!("请设置{}的分档阈值", name));
+                    }
+                    if thresholds.windows(2).any(|w| w[0] >= w[1]) {
+                        return Err(format!("{}的分档阈值必须从小到大排列", name));
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// 行的购买日期；只有月份时取该月最后一天
+fn row_date(store: &ColumnarStore, i: usize) -> Option<NaiveDate> {
+    store.date(i).or_else(|| {
+        let (year, month) = parse_year_month(store.month(i)?)?;
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+    })
+}
+
+/// 按排名等分评分，数值越大得分越高；相同数值得分相同
+fn quantile_scores(values: &[f64], bins: u32) -> Vec<u32> {
+    let n = values.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut scores = vec![1; n];
+    let mut start = 0;
+    while start < n {
+        let mut end = start;
+        while end + 1 < n && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        let score = (start * bins as usize / n) as u32 + 1;
+        for &idx in &order[start..=end] {
+            scores[idx] = score;
+        }
+        start = end + 1;
+    }
+    scores
+}
+
+/// 按阈值评分：达到第 k 个阈值得 k + 1 分
+fn threshold_score(value: f64, thresholds: &[f64]) -> u32 {
+    1 + thresholds.iter().filter(|t| value >= **t).count() as u32
+}
+
+struct CustomerAccumulator {
+    customer: u32,
+    name: u32,
+    last_purchase: NaiveDate,
+    frequency: u32,
+    monetary: Money,
+}
+
+/// 计算每个客户的最近购买（R）、购买次数（F）、购买金额（M）得分并划分客户分群
+///
+/// 有客户主数据时按标准客户合并编码；筛选条件中的 RFM 分群不在此处生效。
+pub fn analyze(
+    store: &ColumnarStore,
+    options: &RfmOptions,
+    filter: Option<&RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<RfmResult, String> {
+    let start_time = std::time::Instant::now();
+    options.validate()?;
+
+    let reference_date = match options.reference_date.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("参考日期格式错误: {}", date))?,
+        None => (0..store.len())
+            .filter_map(|i| row_date(store, i))
+            .max()
+            .ok_or("数据中没有日期或月份，无法计算最近购买时间")?,
+    };
+
+    let compiled = filter.filter(|f| !f.is_empty()).map(|f| f.compile(store));
+    let customer_ids: Option<Vec<u32>> = master.map(|master| master.canonical_ids(store.customer_codes.values()));
+
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    let mut accumulators: Vec<CustomerAccumulator> = Vec::new();
+    let mut excluded_rows = 0;
+    for i in 0..store.len() {
+        if compiled.as_ref().is_some_and(|f| !f.matches(i)) {
+            continue;
+        }
+        let Some(date) = row_date(store, i).filter(|date| *date <= reference_date) else {
+            excluded_rows += 1;
+            continue;
+        };
+        let key = match &customer_ids {
+            Some(ids) => ids[store.customer[i] as usize],
+            None => store.customer[i],
+        };
+        let idx = *index.entry(key).or_insert_with(|| {
+            accumulators.push(CustomerAccumulator {
+                customer: store.customer[i],
+                name: NONE_ID,
+                last_purchase: date,
+                frequency: 0,
+                monetary: Money::ZERO,
+            });
+            accumulators.len() - 1
+        });
+        let acc = &mut accumulators[idx];
+        if acc.name == NONE_ID {
+            acc.name = store.customer_name[i];
+        }
+        acc.last_purchase = acc.last_purchase.max(date);
+        acc.frequency += 1;
+        acc.monetary += store.total_amount(i);
+    }
+
+    let recency: Vec<i64> = accumulators
+        .iter()
+        .map(|acc| (reference_date - acc.last_purchase).num_days())
+        .collect();
+    let (r_scores, f_scores, m_scores): (Vec<u32>, Vec<u32>, Vec<u32>) = match options.binning {
+        RfmBinning::Quantile => (
+            // 天数越少得分越高
+            quantile_scores(&recency.iter().map(|d| -(*d as f64)).collect::<Vec<_>>(), options.bins),
+            quantile_scores(&accumulators.iter().map(|acc| acc.frequency as f64).collect::<Vec<_>>(), options.bins),
+            quantile_scores(&accumulators.iter().map(|acc| acc.monetary.to_yuan()).collect::<Vec<_>>(), options.bins),
+        ),
+        RfmBinning::Threshold => {
+            let top = options.recency_thresholds.len() as u32 + 1;
+            (
+                recency
+                    .iter()
+                    .map(|d| top + 1 - threshold_score(*d as f64, &options.recency_thresholds))
+                    .collect(),
+                accumulators
+                    .iter()
+                    .map(|acc| threshold_score(acc.frequency as f64, &options.frequency_thresholds))
+                    .collect(),
+                accumulators
+                    .iter()
+                    .map(|acc| threshold_score(acc.monetary.to_yuan(), &options.monetary_thresholds))
+                    .collect(),
+            )
+        },
+    };
+
+    let average = |scores: &[u32]| -> f64 {
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().map(|s| *s as f64).sum::<f64>() / scores.len() as f64
+        }
+    };
+    let (average_r_score, average_f_score, average_m_score) = (average(&r_scores), average(&f_scores), average(&m_scores));
+
+    let mut customers: Vec<RfmCustomer> = accumulators
+        .iter()
+        .enumerate()
+        .map(|(idx, acc)| {
+            let (r, f, m) = (r_scores[idx], f_scores[idx], m_scores[idx]);
+            let high = (r as f64 > average_r_score, f as f64 > average_f_score, m as f64 > average_m_score);
+            let segment = SEGMENTS
+                .iter()
+                .find(|(r_high, f_high, m_high, _)| (*r_high, *f_high, *m_high) == high)
+                .map(|(_, _, _, name)| *name)
+                .unwrap_or_default();
+
+            let code = store.customer_codes.get(acc.customer).unwrap_or_default();
+            let name = master
+                .and_then(|master| master.canonical_name(code))
+                .or_else(|| store.customer_names.get(acc.name))
+                .unwrap_or_default();
+
+            RfmCustomer {
+                customer_code: master.map_or(code, |master| master.canonical_code(code)).to_string(),
+                customer_name: name.to_string(),
+                last_purchase: acc.last_purchase.format("%Y-%m-%d").to_string(),
+                recency_days: recency[idx],
+                frequency: acc.frequency,
+                monetary: acc.monetary,
+                r_score: r,
+                f_score: f,
+                m_score: m,
+                rfm_score: format!("{}{}{}", r, f, m),
+                segment: segment.to_string(),
+            }
+        })
+        .collect();
+    customers.sort_by(|a, b| {
+        b.monetary
+            .cmp(&a.monetary)
+            .then_with(|| a.customer_code.cmp(&b.customer_code))
+    });
+
+    let total_amount: Money = customers.iter().map(|c| c.monetary).sum();
+    let segments: Vec<RfmSegmentSummary> = SEGMENTS
+        .iter()
+        .map(|(_, _, _, segment)| {
+            let members: Vec<&RfmCustomer> = customers.iter().filter(|c| c.segment == *segment).collect();
+            let amount: Money = members.iter().map(|c| c.monetary).sum();
+            let count = members.len() as f64;
+            RfmSegmentSummary {
+                segment: segment.to_string(),
+                customer_count: members.len(),
+                customer_share: percent_of(count, customers.len() as f64),
+                total_amount: amount,
+                amount_share: percent_of(amount.to_yuan(), total_amount.to_yuan()),
+                average_recency_days: if members.is_empty() { 0.0 } else { members.iter().map(|c| c.recency_days as f64).sum::<f64>() / count },
+                average_frequency: if members.is_empty() { 0.0 } else { members.iter().map(|c| c.frequency as f64).sum::<f64>() / count },
+            }
+        })
+        .collect();
+
+    Ok(RfmResult {
+        options: options.clone(),
+        reference_date: reference_date.format("%Y-%m-%d").to_string(),
+        customers,
+        segments,
+        average_r_score,
+        average_f_score,
+        average_m_score,
+        excluded_rows,
+        process_time_ms: start_time.elapsed().as_millis(),
+    })
+}
+
+/// 将筛选条件中的 RFM 客户分群展开为客户编码（与已有的客户分组取交集）
+///
+/// RFM 得分按筛选条件中的其他条件（地区、月份等）计算。
+pub fn resolve_filter(
+    store: &ColumnarStore,
+    filter: Option<RowFilter>,
+    master: Option<&CustomerMaster>,
+) -> Result<Option<RowFilter>, String> {
+    let Some(mut filter) = filter else {
+        return Ok(None);
+    };
+    let Some(segment_filter) = filter.rfm.take() else {
+        return Ok(Some(filter));
+    };
+    if segment_filter.segments.is_empty() {
+        return Ok(Some(filter));
+    }
+
+    let result = analyze(store, &segment_filter.options, Some(&filter), master)?;
+    let mut codes: BTreeSet<String> = BTreeSet::new();
+    for customer in result.customers.iter().filter(|c| segment_filter.segments.contains(&c.segment)) {
+        match master {
+            Some(master) => codes.extend(master.codes_for(&customer.customer_code).into_iter().map(str::to_string)),
+            None => {
+                codes.insert(customer.customer_code.clone());
+            },
+        }
+    }
+    if !filter.customer_codes.is_empty() {
+        // 按标准编码比较，客户分组中的编码可能是同一客户的其他别名
+        let canonical = |code: &str| master.map_or(code.to_string(), |master| master.canonical_code(code).to_string());
+        let group: HashSet<String> = filter.customer_codes.iter().map(|code| canonical(code)).collect();
+        codes.retain(|code| group.contains(&canonical(code)));
+    }
+    // 分群中没有客户时结果为空，而不是不限客户
+    filter.match_none = codes.is_empty();
+    filter.customer_codes = codes.into_iter().collect();
+    Ok(Some(filter))
+}
+
+/// 导出 RFM 结果为Excel文件（客户得分、分群汇总两个工作表）
+pub fn export_xlsx(result: &RfmResult, file_path: &str) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let integer = Format::new().set_num_format("#,##0");
+    let amount = Format::new().set_num_format("#,##0.00");
+    let percent = Format::new().set_num_format("0.00\"%\"");
+    let write_err = |e: rust_xlsxwriter::XlsxError| format!("写入数据失败: {}", e);
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("客户RFM").map_err(write_err)?;
+    let headers = [
+        "客户编码", "客户名称", "最近购买日期", "距参考日期天数", "购买次数", "购买金额",
+        "R", "F", "M", "RFM", "客户分群",
+    ];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &bold).map_err(write_err)?;
+    }
+    for (index, customer) in result.customers.iter().enumerate() {
+        let row = index as u32 + 1;
+        worksheet.write_string(row, 0, &customer.customer_code).map_err(write_err)?;
+        worksheet.write_string(row, 1, &customer.customer_name).map_err(write_err)?;
+        worksheet.write_string(row, 2, &customer.last_purchase).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 3, customer.recency_days as f64, &integer).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 4, customer.frequency as f64, &integer).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 5, customer.monetary.to_yuan(), &amount).map_err(write_err)?;
+        for (col, score) in [(6, customer.r_score), (7, customer.f_score), (8, customer.m_score)] {
+            worksheet.write_number(row, col, score as f64).map_err(write_err)?;
+        }
+        worksheet.write_string(row, 9, &customer.rfm_score).map_err(write_err)?;
+        worksheet.write_string(row, 10, &customer.segment).map_err(write_err)?;
+    }
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("分群汇总").map_err(write_err)?;
+    let headers = ["客户分群", "客户数", "客户占比", "购买金额", "金额占比", "平均距今天数", "平均购买次数"];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &bold).map_err(write_err)?;
+    }
+    for (index, segment) in result.segments.iter().enumerate() {
+        let row = index as u32 + 1;
+        worksheet.write_string(row, 0, &segment.segment).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 1, segment.customer_count as f64, &integer).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 2, segment.customer_share, &percent).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 3, segment.total_amount.to_yuan(), &amount).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 4, segment.amount_share, &percent).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 5, segment.average_recency_days, &amount).map_err(write_err)?;
+        worksheet.write_number_with_format(row, 6, segment.average_frequency, &amount).map_err(write_err)?;
+    }
+
+    workbook.save(file_path).map_err(|e| format!("保存Excel文件失败: {}", e))
+}
@@ -0,0 +1,158 @@
+use crate::aggregate_cube::month_in_range;
+use crate::columnar_store::{ColumnarStore, Dictionary, NONE_ID};
+use crate::customer_master::CustomerMaster;
+use crate::excel_processor::CustomerData;
+use crate::money::Money;
+use crate::monthly_analysis::CUSTOM_DIMENSION_PREFIX;
+use crate::rfm::RfmSegmentFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// 行筛选条件（各条件之间为"且"，同一条件的多个取值之间为"或"，空列表表示不限）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RowFilter {
+    // 月份区间（格式 "2024-01"，含首尾）
+    pub start_month: Option<String>,
+    pub end_month: Option<String>,
+    pub provinces: Vec<String>,
+    pub cities: Vec<String>,
+    pub districts: Vec<String>,
+    pub regions: Vec<String>,
+    // 自定义维度：列名 → 取值
+    pub custom: BTreeMap<String, Vec<String>>,
+    // 客户分组（客户编码列表）
+    pub customer_codes: Vec<String>,
+    // RFM 客户分群，使用前由 rfm::resolve_filter 展开为客户编码
+    pub rfm: Option<RfmSegmentFilter>,
+    // RFM 客户分群展开后没有客户：任何行都不满足条件
+    #[serde(skip)]
+    pub match_none: bool,
+}
+
+impl RowFilter {
+    /// 是否没有任何筛选条件
+    pub fn is_empty(&self) -> bool {
+        self.start_month.is_none()
+            && self.end_month.is_none()
+            && self.provinces.is_empty()
+            && self.cities.is_empty()
+            && self.districts.is_empty()
+            && self.regions.is_empty()
+            && self.custom.values().all(|values| values.is_empty())
+            && self.customer_codes.is_empty()
+            && self.rfm.as_ref().is_none_or(|rfm| rfm.segments.is_empty())
+            && !self.match_none
+    }
+
+    /// 将客户分组中的编码展开为同一标准客户的所有原始编码
+    ///
+    /// 界面中显示的是标准编码，数据中按别名编码保存的行也需要匹配。
+    pub fn expand_customer_codes(&mut self, master: &CustomerMaster) {
+        let codes: BTreeSet<&str> = self
+            .customer_codes
+            .iter()
+            .flat_map(|code| master.codes_for(code))
+            .collect();
+        self.customer_codes = codes.into_iter().map(str::to_string).collect();
+    }
+
+    /// 针对某个存储编译为按字典ID判断的筛选器
+    pub fn compile<'a>(&self, store: &'a ColumnarStore) -> CompiledFilter<'a> {
+        let mut conditions: Vec<(&'a [u32], Vec<bool>)> = Vec::new();
+        if self.match_none {
+            conditions.push((&[], Vec::new()));
+        }
+
+        let mut add = |analysis_type: &str, values: &[String]| {
+            if values.is_empty() {
+                return;
+            }
+            match store.dimension(analysis_type) {
+                Some((column, dict)) => conditions.push((column, allowed_ids(dict, values))),
+                // 存储中没有该列时任何行都不满足条件
+                None => conditions.push((&[], Vec::new())),
+            }
+        };
+
+        add("province", &self.provinces);
+        add("city", &self.cities);
+        add("district", &self.districts);
+        add("region", &self.regions);
+        add("customer", &self.customer_codes);
+        for (name, values) in &self.custom {
+            add(&format!("{}{}", CUSTOM_DIMENSION_PREFIX, name), values);
+        }
+
+        if self.start_month.is_some() || self.end_month.is_some() {
+            let allowed = store
+                .months
+                .values()
+                .iter()
+                .map(|month| month_in_range(month, self.start_month.as_deref(), self.end_month.as_deref()))
+                .collect();
+            conditions.push((&store.month, allowed));
+        }
+
+        CompiledFilter { conditions }
+    }
+}
+
+fn allowed_ids(dict: &Dictionary, values: &[String]) -> Vec<bool> {
+    let mut allowed = vec![false; dict.len()];
+    for value in values {
+        if let Some(id) = dict.lookup(value) {
+            allowed[id as usize] = true;
+        }
+    }
+    allowed
+}
+
+/// 编译后的筛选器：每个条件为一列字典ID及允许的ID表
+pub struct CompiledFilter<'a> {
+    conditions: Vec<(&'a [u32], Vec<bool>)>,
+}
+
+impl CompiledFilter<'_> {
+    /// 第 i 行是否满足所有条件
+    #[inline]
+    pub fn matches(&self, i: usize) -> bool {
+        self.conditions.iter().all(|(column, allowed)| {
+            column
+                .get(i)
+                .is_some_and(|&id| id != NONE_ID && allowed[id as usize])
+        })
+    }
+}
+
+/// 按客户汇总满足条件的行（用于带筛选条件的排行）
+pub fn customer_totals(store: &ColumnarStore, filter: &RowFilter) -> Vec<CustomerData> {
+    let compiled = filter.compile(store);
+    let mut customers: HashMap<u32, CustomerData> = HashMap::new();
+
+    for i in 0..store.len() {
+        if !compiled.matches(i) {
+            continue;
+        }
+
+        let customer = customers.entry(store.customer[i]).or_insert_with(|| CustomerData {
+            customer_code: store.customer_code(i).to_string(),
+            customer_name: String::new(),
+            pay_amount: Money::ZERO,
+            recharge_deduction: Money::ZERO,
+            total_amount: Money::ZERO,
+            order_count: 0,
+            customer_nature: None,
+            sales_rep: None,
+        });
+        customer.pay_amount += store.pay_amount[i];
+        customer.recharge_deduction += store.recharge_deduction[i];
+        customer.total_amount += store.total_amount(i);
+        customer.order_count += 1;
+        if customer.customer_name.is_empty() {
+            customer.customer_name = store.customer_name(i).to_string();
+        }
+    }
+
+    customers.into_values().collect()
+}
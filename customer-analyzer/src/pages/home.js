@@ -425,7 +425,8 @@ export class HomePage {
         
         try {
             console.log('开始添加数据源...');
-            const result = await invoke('add_data_source', { filePath: filePath });
+            const jobId = await invoke('add_data_source', { filePath: filePath });
+            const result = await invoke('wait_job', { jobId });
             console.log('添加数据源结果:', result);
             
             // 隐藏进度提示
@@ -474,7 +475,8 @@ export class HomePage {
                 }
                 
                 try {
-                    const result = await invoke('add_data_source', { filePath: filePath });
+                    const jobId = await invoke('add_data_source', { filePath: filePath });
+                    await invoke('wait_job', { jobId });
                     successCount++;
                     
                     // 更新文件进度为完成
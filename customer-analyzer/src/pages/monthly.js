@@ -98,6 +98,27 @@ export class MonthlyPage {
                             </div>
                         </div>
                         
+                        <!-- 时间粒度 -->
+                        <div class="filter-row">
+                            <div class="filter-item">
+                                <label>📅 时间粒度：</label>
+                                <select id="granularitySelect" class="select-input">
+                                    <option value="day">按日</option>
+                                    <option value="week">按周</option>
+                                    <option value="month" selected>按月</option>
+                                    <option value="quarter">按季度</option>
+                                    <option value="year">按年</option>
+                                </select>
+                            </div>
+                            <div class="filter-item">
+                                <label>🗓️ 财年起始月：</label>
+                                <select id="fiscalStartSelect" class="select-input">
+                                    ${Array.from({ length: 12 }, (_, i) =>
+                                        `<option value="${i + 1}">${i + 1}月</option>`).join('')}
+                                </select>
+                            </div>
+                        </div>
+                        
                         <button class="btn btn-primary" id="analyzeBtn" disabled>
                             <span>🔍</span>
                             开始分析
@@ -125,7 +146,7 @@ export class MonthlyPage {
                                 <div class="stat-value" id="totalOrders">0</div>
                             </div>
                             <div class="stat-card">
-                                <div class="stat-label">月份数</div>
+                                <div class="stat-label">周期数</div>
                                 <div class="stat-value" id="monthCount">0</div>
                             </div>
                             <div class="stat-card">
@@ -161,6 +182,8 @@ export class MonthlyPage {
                                     <th style="text-align: right;">充值抵扣</th>
                                     <th style="text-align: right;">总金额</th>
                                     <th style="text-align: right;">环比增长</th>
+                                    <th style="text-align: right;">同比增长</th>
+                                    <th style="text-align: right;">本年累计</th>
                                 </tr>
                             </thead>
                             <tbody id="resultTable"></tbody>
@@ -523,9 +546,8 @@ export class MonthlyPage {
             
             let result;
             if (selectedIds.length === 1) {
-                // 单个数据源，使用原有逻辑
-                await invoke('switch_data_source', { dataSourceId: selectedIds[0] });
-                result = await invoke('get_monthly_options');
+                // 单个数据源
+                result = await invoke('get_monthly_options', { dataSourceId: selectedIds[0] });
             } else {
                 // 多个数据源，使用合并选项
                 result = await invoke('get_monthly_options_multi', { dataSourceIds: selectedIds });
@@ -829,6 +851,11 @@ export class MonthlyPage {
         const activeTab = document.querySelector('.option-tab.active');
         const analysisType = activeTab?.dataset.type || 'customer';
         
+        const options = {
+            granularity: document.getElementById('granularitySelect').value,
+            fiscal_year_start_month: parseInt(document.getElementById('fiscalStartSelect').value, 10)
+        };
+        
         const analyzeBtn = document.getElementById('analyzeBtn');
         analyzeBtn.disabled = true;
         analyzeBtn.innerHTML = '<span>⏳</span> 分析中...';
@@ -845,18 +872,20 @@ export class MonthlyPage {
             
             let result;
             if (selectedIds.length === 1) {
-                // 单个数据源，使用原有逻辑
-                await invoke('switch_data_source', { dataSourceId: selectedIds[0] });
+                // 单个数据源
                 result = await invoke('analyze_monthly_cached', {
+                    dataSourceId: selectedIds[0],
                     analysisType,
-                    target
+                    target,
+                    options
                 });
             } else {
                 // 多个数据源，使用合并分析
                 result = await invoke('analyze_monthly_multi', {
                     dataSourceIds: selectedIds,
                     analysisType,
-                    target
+                    target,
+                    options
                 });
             }
             
@@ -883,9 +912,16 @@ export class MonthlyPage {
         const typeText = typeTextMap[analysisType] || '维度';
         
         const targetName = result.target_name || result.target;
-        document.getElementById('resultTitle').textContent = `${targetName} 月度销售趋势`;
+        const granularityText = {
+            'day': '每日',
+            'week': '每周',
+            'month': '月度',
+            'quarter': '季度',
+            'year': '年度'
+        }[result.options.granularity] || '月度';
+        document.getElementById('resultTitle').textContent = `${targetName} ${granularityText}销售趋势`;
         document.getElementById('resultSubtitle').textContent = 
-            `${typeText}分析 · 共 ${result.monthly_data.length} 个月`;
+            `${typeText}分析 · 共 ${result.monthly_data.length} 个周期`;
         
         document.getElementById('totalAmount').textContent = 
             '¥' + result.total_amount.toLocaleString('zh-CN', {
@@ -899,7 +935,10 @@ export class MonthlyPage {
         document.getElementById('processTime').textContent = 
             result.process_time_ms + 'ms';
         
-        this.renderTable(result.monthly_data);
+        // 未知月份单独列在表格末尾，不进入趋势图
+        this.renderTable(result.unknown_month
+            ? [...result.monthly_data, result.unknown_month]
+            : result.monthly_data);
         this.renderChart(result.monthly_data);
         
         document.getElementById('resultSection').classList.add('visible');
@@ -918,6 +957,11 @@ export class MonthlyPage {
             const growthText = item.mom_growth_rate !== 0 
                 ? `${item.mom_growth_rate > 0 ? '+' : ''}${item.mom_growth_rate.toFixed(2)}%`
                 : '-';
+            const yoy = item.yoy_growth_rate;
+            const yoyClass = yoy > 0 ? 'growth-positive' : yoy < 0 ? 'growth-negative' : '';
+            const yoyText = yoy !== null && yoy !== undefined
+                ? `${yoy > 0 ? '+' : ''}${yoy.toFixed(2)}%`
+                : '-';
             
             const tr = document.createElement('tr');
             tr.innerHTML = `
@@ -942,6 +986,13 @@ export class MonthlyPage {
                     })}
                 </td>
                 <td style="text-align: right;" class="${growthClass}">${growthText}</td>
+                <td style="text-align: right;" class="${yoyClass}">${yoyText}</td>
+                <td style="text-align: right;">
+                    ¥${item.ytd_amount.toLocaleString('zh-CN', {
+                        minimumFractionDigits: 2,
+                        maximumFractionDigits: 2
+                    })}
+                </td>
             `;
             tbody.appendChild(tr);
         });
@@ -1129,14 +1180,24 @@ export class MonthlyPage {
         const { save } = window.__TAURI__.dialog;
         
         const result = this.analysisResult;
-        const headers = ['月份', '订单数', '支付金额', '充值抵扣', '总金额', '环比增长率'];
-        const rows = result.monthly_data.map(item => [
+        const headers = ['周期', '订单数', '支付金额', '充值抵扣', '总金额', '环比增长率', '去年同期', '同比增长率', '本年累计', '去年同期累计', '累计同比'];
+        const optionalAmount = value => value === null || value === undefined ? '' : value.toFixed(2);
+        const optionalRate = value => value === null || value === undefined ? '' : value.toFixed(2) + '%';
+        const exportData = result.unknown_month
+            ? [...result.monthly_data, result.unknown_month]
+            : result.monthly_data;
+        const rows = exportData.map(item => [
             this.formatMonth(item.month),
             item.order_count,
             item.pay_amount.toFixed(2),
             item.recharge_deduction.toFixed(2),
             item.total_amount.toFixed(2),
-            item.mom_growth_rate.toFixed(2) + '%'
+            item.mom_growth_rate.toFixed(2) + '%',
+            optionalAmount(item.last_year_amount),
+            optionalRate(item.yoy_growth_rate),
+            item.ytd_amount.toFixed(2),
+            optionalAmount(item.last_year_ytd_amount),
+            optionalRate(item.ytd_growth_rate)
         ]);
         
         // 添加BOM以支持中文
@@ -1211,7 +1272,14 @@ export class MonthlyPage {
                 }
             }
             
+            const selectedIds = Array.from(document.querySelectorAll('.ds-checkbox:checked')).map(cb => cb.value);
+            if (selectedIds.length === 0) {
+                this.showError('请至少选择一个数据源');
+                return;
+            }
+            
             const orderDetails = await invoke('get_order_details', {
+                dataSourceIds: selectedIds,
                 analysisType,
                 target
             });
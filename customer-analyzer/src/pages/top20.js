@@ -386,9 +386,8 @@ export class Top20Page {
         try {
             let result;
             if (selectedIds.length === 1) {
-                // 单个数据源，使用原有逻辑
-                await invoke('switch_data_source', { dataSourceId: selectedIds[0] });
-                result = await invoke('analyze_top20_cached');
+                // 单个数据源
+                result = await invoke('analyze_top20_cached', { dataSourceId: selectedIds[0] });
             } else {
                 // 多个数据源，使用合并分析
                 result = await invoke('analyze_top20_multi', { dataSourceIds: selectedIds });
@@ -410,7 +409,7 @@ export class Top20Page {
     }
     
     handleResult(result) {
-        this.processedData = result.top20;
+        this.processedData = result.top_customers;
         this.totalAmountAll = result.total_amount;
         
         // 更新统计信息
@@ -423,13 +422,13 @@ export class Top20Page {
             });
         document.getElementById('top20Percentage').textContent = 
             result.total_amount > 0 
-                ? ((result.top20_amount / result.total_amount) * 100).toFixed(2) + '%'
+                ? ((result.top_amount / result.total_amount) * 100).toFixed(2) + '%'
                 : '0%';
         document.getElementById('processTime').textContent = 
             result.process_time_ms + 'ms';
         
         // 渲染表格
-        this.renderTable(result.top20);
+        this.renderTable(result.top_customers);
         
         // 显示结果区域
         const resultSection = document.getElementById('resultSection');
@@ -446,11 +445,9 @@ export class Top20Page {
         const tbody = document.getElementById('resultTable');
         tbody.innerHTML = '';
         
-        data.forEach((customer, index) => {
-            const rank = index + 1;
-            const percentage = this.totalAmountAll > 0 
-                ? ((customer.total_amount / this.totalAmountAll) * 100).toFixed(2)
-                : 0;
+        data.forEach((customer) => {
+            const rank = customer.rank;
+            const percentage = customer.share.toFixed(2);
             
             let rankClass = 'rank-other';
             if (rank === 1) rankClass = 'rank-1';
@@ -574,18 +571,17 @@ export class Top20Page {
         const { save } = window.__TAURI__.dialog;
         
         // 生成CSV数据
-        const headers = ['排名', '客户编码', '客户名称', '订单数', '支付金额', '充值抵扣', '总金额', '占比'];
-        const rows = this.processedData.map((customer, index) => [
-            index + 1,
+        const headers = ['排名', '客户编码', '客户名称', '订单数', '支付金额', '充值抵扣', '总金额', '占比', '累计占比'];
+        const rows = this.processedData.map((customer) => [
+            customer.rank,
             customer.customer_code,
             customer.customer_name,
             customer.order_count,
             customer.pay_amount.toFixed(2),
             customer.recharge_deduction.toFixed(2),
             customer.total_amount.toFixed(2),
-            this.totalAmountAll > 0 
-                ? ((customer.total_amount / this.totalAmountAll) * 100).toFixed(2) + '%'
-                : '0%'
+            customer.share.toFixed(2) + '%',
+            customer.cumulative_share.toFixed(2) + '%'
         ]);
         
         // 添加BOM以支持中文